@@ -0,0 +1,748 @@
+use crate::{check_arbitrage, fee::multiply, pools::PoolFilter, ArbitrageOpportunity, Mode};
+use clap::Args;
+use color_eyre::eyre::{eyre, Result};
+use ekubo::{models::Quotes, TokenAmount};
+use serde_json::{json, Value};
+use starknet::core::types::Felt;
+use std::cmp::Reverse;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+// 2^251 is the largest power of 2 below the field prime
+const MAX_POWER_OF_2: u8 = 251;
+
+/// Quotes of the token to itself for every amount of a scan round
+pub struct Snapshot {
+    pub block: Option<u64>,
+    pub token_address: Felt,
+    pub quotes: Vec<(Felt, Quotes)>,
+}
+
+impl Snapshot {
+    /// Saved as `<unix timestamp in ms>.json` in the directory
+    pub fn save(&self, dir: &Path) -> Result<()> {
+        let timestamp = SystemTime::now().duration_since(UNIX_EPOCH)?.as_millis();
+        let snapshot = json!({
+            "timestamp": timestamp,
+            "block": self.block,
+            "token": format!("{:#x}", self.token_address),
+            "quotes": self
+                .quotes
+                .iter()
+                .map(|(amount, quotes)| json!({ "amount": amount.to_string(), "quotes": quotes }))
+                .collect::<Vec<_>>(),
+        });
+        fs::create_dir_all(dir)?;
+        fs::write(
+            dir.join(format!("{timestamp}.json")),
+            serde_json::to_string(&snapshot)?,
+        )?;
+        Ok(())
+    }
+
+    fn load(path: &Path) -> Result<(u128, Self)> {
+        let snapshot: Value = serde_json::from_str(&fs::read_to_string(path)?)?;
+        let invalid = || eyre!("invalid snapshot {}", path.display());
+        let timestamp = snapshot["timestamp"].as_u64().ok_or_else(invalid)?;
+        let token_address = Felt::from_hex(snapshot["token"].as_str().ok_or_else(invalid)?)?;
+        let quotes = snapshot["quotes"]
+            .as_array()
+            .ok_or_else(invalid)?
+            .iter()
+            .map(|entry| {
+                let amount = Felt::from_dec_str(entry["amount"].as_str().ok_or_else(invalid)?)?;
+                let quotes: Quotes = serde_json::from_value(entry["quotes"].clone())?;
+                Ok((amount, quotes))
+            })
+            .collect::<Result<_>>()?;
+        Ok((
+            timestamp.into(),
+            Self {
+                block: snapshot["block"].as_u64(),
+                token_address,
+                quotes,
+            },
+        ))
+    }
+}
+
+/// Snapshots of the directory in the recording order
+pub fn load_snapshots(dir: &Path) -> Result<Vec<Snapshot>> {
+    let mut snapshots = vec![];
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            snapshots.push(Snapshot::load(&path)?);
+        }
+    }
+    snapshots.sort_by_key(|(timestamp, _)| *timestamp);
+    Ok(snapshots
+        .into_iter()
+        .map(|(_, snapshot)| snapshot)
+        .collect())
+}
+
+fn parse_wei(value: &str) -> Result<Felt, String> {
+    Felt::from_dec_str(value).map_err(|e| e.to_string())
+}
+
+fn parse_amount(value: &str) -> Result<TokenAmount, String> {
+    value.parse().map_err(|e: color_eyre::Report| e.to_string())
+}
+
+// larger powers of 2 wrap around the field prime
+fn parse_power_of_2(value: &str) -> Result<u8, String> {
+    let power: u8 = value
+        .parse()
+        .map_err(|e: std::num::ParseIntError| e.to_string())?;
+    if power > MAX_POWER_OF_2 {
+        return Err(format!("a power of 2 should be at most {MAX_POWER_OF_2}"));
+    }
+    Ok(power)
+}
+
+#[derive(Args)]
+pub struct ReplayArgs {
+    /// Directory with quote snapshots recorded by the bot with `RECORD_QUOTES_DIR`
+    pub dir: PathBuf,
+    /// Bot mode
+    #[arg(long, value_enum, default_value = "simple")]
+    pub mode: Mode,
+    /// Balance of the account in simple mode, WEI (unlimited by default)
+    #[arg(long, value_parser = parse_wei)]
+    pub balance: Option<Felt>,
+    /// Fee of a transaction, WEI
+    #[arg(long, default_value = "1000000000000000", value_parser = parse_wei)]
+    pub fee: Felt,
+    /// Additional fee of every hop of the route, WEI
+    #[arg(long, default_value = "0", value_parser = parse_wei)]
+    pub fee_per_hop: Felt,
+}
+
+#[derive(Args)]
+pub struct BacktestArgs {
+    #[command(flatten)]
+    pub replay: ReplayArgs,
+    /// Minimum profits to compare, WEI
+    #[arg(long, value_delimiter = ',', default_value = "0", value_parser = parse_amount)]
+    pub min_profit: Vec<TokenAmount>,
+    /// Maximum splits to compare (0 is a single route)
+    #[arg(long, value_delimiter = ',', default_value = "0")]
+    pub max_splits: Vec<u8>,
+    /// Maximum hops to compare
+    #[arg(long, value_delimiter = ',', default_value = "2")]
+    pub max_hops: Vec<u8>,
+    /// Number of the most profitable quotes which are checked for a strategy
+    #[arg(long, default_value_t = 5)]
+    pub num_top_quotes: usize,
+    /// The fee limit is the estimated fee times the multiplier
+    #[arg(long, default_value_t = 2.0)]
+    pub fee_multiplier: f64,
+}
+
+#[derive(Args)]
+pub struct SweepArgs {
+    #[command(flatten)]
+    pub replay: ReplayArgs,
+    /// Minimum profit, WEI
+    #[arg(long, default_value = "0", value_parser = parse_amount)]
+    pub min_profit: TokenAmount,
+    /// The smallest quoted amounts (powers of 2) to compare
+    #[arg(long, value_delimiter = ',', default_value = "53", value_parser = parse_power_of_2)]
+    pub min_power_of_2: Vec<u8>,
+    /// The exclusive upper bounds of quoted amounts (powers of 2) to compare
+    #[arg(long, value_delimiter = ',', default_value = "65", value_parser = parse_power_of_2)]
+    pub max_power_of_2: Vec<u8>,
+    /// Maximum splits to compare (0 is a single route)
+    #[arg(long, value_delimiter = ',', default_value = "0")]
+    pub max_splits: Vec<u8>,
+    /// Maximum hops to compare
+    #[arg(long, value_delimiter = ',', default_value = "2")]
+    pub max_hops: Vec<u8>,
+    /// Numbers of the most profitable quotes which are checked for a strategy
+    #[arg(long, value_delimiter = ',', default_value = "5")]
+    pub num_top_quotes: Vec<usize>,
+    /// Fee multipliers to compare
+    #[arg(long, value_delimiter = ',', default_value = "2")]
+    pub fee_multiplier: Vec<f64>,
+    /// Write the ranked table as CSV
+    #[arg(long)]
+    pub csv: Option<PathBuf>,
+}
+
+/// Strategy settings compared by a backtest
+#[derive(Clone, Copy)]
+pub struct Setting {
+    pub min_profit: TokenAmount,
+    pub min_power_of_2: u8,
+    pub max_power_of_2: u8,
+    pub max_splits: u8,
+    pub max_hops: u8,
+    pub num_top_quotes: usize,
+    pub fee_multiplier: f64,
+}
+
+impl Setting {
+    fn quotes_amount(&self, amount: Felt) -> bool {
+        amount >= Felt::TWO.pow(self.min_power_of_2) && amount < Felt::TWO.pow(self.max_power_of_2)
+    }
+}
+
+/// Estimated fee of an arbitrage transaction
+#[derive(Clone, Copy)]
+pub struct FeeModel {
+    pub fee: Felt,
+    pub fee_per_hop: Felt,
+}
+
+impl FeeModel {
+    fn estimate(&self, opportunity: &ArbitrageOpportunity) -> Felt {
+        let hops = opportunity
+            .quotes
+            .splits
+            .iter()
+            .map(|split| split.route.len())
+            .sum::<usize>();
+        self.fee + self.fee_per_hop * Felt::from(hops)
+    }
+}
+
+/// An opportunity taken in a snapshot
+pub struct Trade {
+    pub block: Option<u64>,
+    pub amount: TokenAmount,
+    pub route_id: Felt,
+    pub profit: TokenAmount,
+    pub fee: Felt,
+}
+
+pub struct Report {
+    pub setting: Setting,
+    pub snapshots: usize,
+    /// quote requests to the API
+    pub quotes: usize,
+    pub trades: Vec<Trade>,
+    /// profits minus fees of the trades, signed
+    pub pnl: Felt,
+}
+
+impl Report {
+    pub fn hit_rate(&self) -> f64 {
+        if self.snapshots == 0 {
+            return 0.0;
+        }
+        self.trades.len() as f64 / self.snapshots as f64
+    }
+}
+
+/// Replays snapshots with the same opportunity checks as the bot.
+/// Quotes recorded with more splits or hops than the setting allows are skipped
+/// (the API would return another route), so snapshots should be recorded with the widest settings.
+/// Like the bot with one executor, only the best opportunity of a snapshot is tried.
+pub fn replay(
+    snapshots: &[Snapshot],
+    setting: Setting,
+    fee_model: FeeModel,
+    mode: Mode,
+    balance: Option<Felt>,
+    pools: &PoolFilter,
+) -> Report {
+    let mut report = Report {
+        setting,
+        snapshots: snapshots.len(),
+        quotes: 0,
+        trades: vec![],
+        pnl: Felt::ZERO,
+    };
+    for snapshot in snapshots {
+        let quoted: Vec<&(Felt, Quotes)> = snapshot
+            .quotes
+            .iter()
+            .filter(|(amount, _)| setting.quotes_amount(*amount))
+            .filter(|(amount, _)| match (mode, balance) {
+                (Mode::Simple, Some(balance)) => *amount <= balance,
+                _ => true,
+            })
+            .collect();
+        report.quotes += quoted.len();
+        let mut opportunities: Vec<ArbitrageOpportunity> = quoted
+            .into_iter()
+            .filter(|(_, quotes)| {
+                quotes.splits.len() <= usize::from(setting.max_splits).max(1)
+                    && quotes
+                        .splits
+                        .iter()
+                        .all(|split| split.route.len() <= usize::from(setting.max_hops))
+            })
+            .filter_map(|(amount, quotes)| {
+                check_arbitrage(*amount, quotes.clone(), setting.min_profit, pools)
+            })
+            .collect();
+        opportunities.sort_unstable_by_key(|opportunity| Reverse(opportunity.profit));
+        let Some(opportunity) =
+            opportunities
+                .into_iter()
+                .take(setting.num_top_quotes)
+                .find(|opportunity| {
+                    opportunity
+                        .minimum_output(setting.min_profit, Felt::ZERO)
+                        .and_then(|minimum_output| {
+                            opportunity.clone().into_strategy(
+                                Felt::ZERO,
+                                snapshot.token_address,
+                                mode,
+                                minimum_output,
+                                Felt::ZERO,
+                                TokenAmount::zero(),
+                            )
+                        })
+                        .is_some()
+                })
+        else {
+            continue;
+        };
+        let fee = fee_model.estimate(&opportunity);
+        // the same check as before sending
+        if opportunity.profit <= TokenAmount::from(multiply(fee, setting.fee_multiplier)) {
+            continue;
+        }
+        let Ok(profit) = Felt::try_from(opportunity.profit) else {
+            continue;
+        };
+        report.pnl += profit - fee;
+        report.trades.push(Trade {
+            block: snapshot.block,
+            amount: opportunity.amount,
+            route_id: opportunity.quotes.route_id(),
+            profit: opportunity.profit,
+            fee,
+        });
+    }
+    report
+}
+
+fn replay_all(args: &ReplayArgs, settings: Vec<Setting>) -> Result<Vec<Report>> {
+    let snapshots = load_snapshots(&args.dir)?;
+    info!(
+        "loaded {} snapshots from {}",
+        snapshots.len(),
+        args.dir.display()
+    );
+    let pools = PoolFilter::from_env()?;
+    let fee_model = FeeModel {
+        fee: args.fee,
+        fee_per_hop: args.fee_per_hop,
+    };
+    Ok(settings
+        .into_iter()
+        .map(|setting| {
+            replay(
+                &snapshots,
+                setting,
+                fee_model,
+                args.mode,
+                args.balance,
+                &pools,
+            )
+        })
+        .collect())
+}
+
+pub fn run(args: &BacktestArgs) -> Result<()> {
+    let mut settings = vec![];
+    for &min_profit in &args.min_profit {
+        for &max_splits in &args.max_splits {
+            for &max_hops in &args.max_hops {
+                settings.push(Setting {
+                    min_profit,
+                    min_power_of_2: 0,
+                    max_power_of_2: MAX_POWER_OF_2,
+                    max_splits,
+                    max_hops,
+                    num_top_quotes: args.num_top_quotes,
+                    fee_multiplier: args.fee_multiplier,
+                });
+            }
+        }
+    }
+    let reports = replay_all(&args.replay, settings)?;
+    print_reports(&reports);
+    print_trades(&reports);
+    Ok(())
+}
+
+pub fn sweep(args: &SweepArgs) -> Result<()> {
+    let reports = sweep_reports(args)?;
+    print_reports(&reports);
+    if let Some(path) = &args.csv {
+        write_csv(&reports, path)?;
+        info!("ranked settings are written to {}", path.display());
+    }
+    Ok(())
+}
+
+/// Every combination of settings ranked by PnL, then by the number of quote requests
+fn sweep_reports(args: &SweepArgs) -> Result<Vec<Report>> {
+    let mut settings = vec![];
+    for &min_power_of_2 in &args.min_power_of_2 {
+        for &max_power_of_2 in &args.max_power_of_2 {
+            if max_power_of_2 <= min_power_of_2 {
+                continue;
+            }
+            for &max_splits in &args.max_splits {
+                for &max_hops in &args.max_hops {
+                    for &num_top_quotes in &args.num_top_quotes {
+                        for &fee_multiplier in &args.fee_multiplier {
+                            settings.push(Setting {
+                                min_profit: args.min_profit,
+                                min_power_of_2,
+                                max_power_of_2,
+                                max_splits,
+                                max_hops,
+                                num_top_quotes,
+                                fee_multiplier,
+                            });
+                        }
+                    }
+                }
+            }
+        }
+    }
+    let mut reports = replay_all(&args.replay, settings)?;
+    reports.sort_by_key(|report| (Reverse(report.pnl.to_bigint()), report.quotes));
+    Ok(reports)
+}
+
+const COLUMNS: [&str; 12] = [
+    "min_profit",
+    "min_power_of_2",
+    "max_power_of_2",
+    "max_splits",
+    "max_hops",
+    "num_top_quotes",
+    "fee_multiplier",
+    "snapshots",
+    "quotes",
+    "trades",
+    "hit_rate",
+    "pnl",
+];
+
+fn row(report: &Report) -> [String; 12] {
+    let setting = &report.setting;
+    [
+        setting.min_profit.to_string(),
+        setting.min_power_of_2.to_string(),
+        setting.max_power_of_2.to_string(),
+        setting.max_splits.to_string(),
+        setting.max_hops.to_string(),
+        setting.num_top_quotes.to_string(),
+        setting.fee_multiplier.to_string(),
+        report.snapshots.to_string(),
+        report.quotes.to_string(),
+        report.trades.len().to_string(),
+        format!("{:.3}", report.hit_rate()),
+        report.pnl.to_bigint().to_string(),
+    ]
+}
+
+fn print_reports(reports: &[Report]) {
+    let rows: Vec<[String; 12]> = reports.iter().map(row).collect();
+    let widths: Vec<usize> = COLUMNS
+        .iter()
+        .enumerate()
+        .map(|(i, column)| {
+            rows.iter()
+                .map(|row| row[i].len())
+                .fold(column.len(), usize::max)
+        })
+        .collect();
+    let line = |cells: Vec<&str>| {
+        cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{cell:>width$}"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    };
+    println!("{}", line(COLUMNS.to_vec()));
+    for row in &rows {
+        println!("{}", line(row.iter().map(String::as_str).collect()));
+    }
+}
+
+// Trades of every setting, the block is unknown if it wasn't recorded
+fn print_trades(reports: &[Report]) {
+    for report in reports {
+        let setting = &report.setting;
+        println!(
+            "\ntrades with min_profit {}, max_splits {}, max_hops {}:",
+            setting.min_profit, setting.max_splits, setting.max_hops
+        );
+        for trade in &report.trades {
+            println!("{}", trade_line(trade));
+        }
+    }
+}
+
+fn trade_line(trade: &Trade) -> String {
+    format!(
+        "block {} amount {} route {:#x} profit {} fee {}",
+        trade
+            .block
+            .map_or_else(|| "?".to_string(), |block| block.to_string()),
+        trade.amount,
+        trade.route_id,
+        trade.profit,
+        trade.fee
+    )
+}
+
+fn write_csv(reports: &[Report], path: &Path) -> Result<()> {
+    fs::write(path, csv(reports))?;
+    Ok(())
+}
+
+fn csv(reports: &[Report]) -> String {
+    let mut csv = COLUMNS.join(",");
+    csv.push('\n');
+    for report in reports {
+        csv.push_str(&row(report).join(","));
+        csv.push('\n');
+    }
+    csv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ETH: &str = "0x49d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7";
+    const FEE: u128 = 1_000_000_000_000_000;
+
+    // A quote of `amount` ETH back to ETH through `hops` pools of ETH and token 0x1
+    fn quote(power_of_2: u8, profit: i128, hops: u64) -> Value {
+        let amount = 1_u128 << power_of_2;
+        let total = amount.checked_add_signed(profit).unwrap();
+        let route: Vec<Value> = (0..hops)
+            .map(|hop| {
+                json!({
+                    "pool_key": {
+                        "token0": "0x1",
+                        "token1": ETH,
+                        "fee": format!("{hop:#x}"),
+                        "tick_spacing": 1000,
+                        "extension": "0x0",
+                    },
+                    "sqrt_ratio_limit": "0x1",
+                    "skip_ahead": 0,
+                })
+            })
+            .collect();
+        json!({
+            "amount": amount.to_string(),
+            "quotes": {
+                "total": total.to_string(),
+                "splits": [{
+                    "specifiedAmount": amount.to_string(),
+                    "amount": total.to_string(),
+                    "route": route,
+                }],
+            },
+        })
+    }
+
+    // Three recorded scan rounds, saved out of the recording order:
+    // * block 5 - 2^53 with 10 fees of profit and 2^54 with a single fee of profit, 2 hops each
+    // * block 6 - 2^55 with 5 fees of profit through 3 hops
+    // * unknown block - 2^53 at a loss
+    fn fixture(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("backtest-{name}-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let fee = FEE as i128;
+        let snapshots = [
+            (
+                3000,
+                json!({"timestamp": 3000, "block": null, "token": ETH, "quotes": [quote(53, -fee, 2)]}),
+            ),
+            (
+                1000,
+                json!({
+                    "timestamp": 1000,
+                    "block": 5,
+                    "token": ETH,
+                    "quotes": [quote(53, 10 * fee, 2), quote(54, fee, 2)],
+                }),
+            ),
+            (
+                2000,
+                json!({"timestamp": 2000, "block": 6, "token": ETH, "quotes": [quote(55, 5 * fee, 3)]}),
+            ),
+        ];
+        for (timestamp, snapshot) in snapshots {
+            fs::write(dir.join(format!("{timestamp}.json")), snapshot.to_string()).unwrap();
+        }
+        fs::write(dir.join("notes.txt"), "not a snapshot").unwrap();
+        dir
+    }
+
+    fn setting(min_power_of_2: u8, max_hops: u8) -> Setting {
+        Setting {
+            min_profit: TokenAmount::zero(),
+            min_power_of_2,
+            max_power_of_2: MAX_POWER_OF_2,
+            max_splits: 0,
+            max_hops,
+            num_top_quotes: 5,
+            fee_multiplier: 2.0,
+        }
+    }
+
+    fn replay_args(dir: PathBuf) -> ReplayArgs {
+        ReplayArgs {
+            dir,
+            mode: Mode::Simple,
+            balance: None,
+            fee: Felt::from(FEE),
+            fee_per_hop: Felt::ZERO,
+        }
+    }
+
+    fn replay_fixture(name: &str, setting: Setting, balance: Option<Felt>) -> Report {
+        let snapshots = load_snapshots(&fixture(name)).unwrap();
+        let fee_model = FeeModel {
+            fee: Felt::from(FEE),
+            fee_per_hop: Felt::ZERO,
+        };
+        let pools = PoolFilter::from_env().unwrap();
+        replay(
+            &snapshots,
+            setting,
+            fee_model,
+            Mode::Simple,
+            balance,
+            &pools,
+        )
+    }
+
+    #[test]
+    fn snapshots_are_loaded_in_the_recording_order() {
+        let snapshots = load_snapshots(&fixture("order")).unwrap();
+        let blocks: Vec<Option<u64>> = snapshots.iter().map(|snapshot| snapshot.block).collect();
+        assert_eq!(blocks, vec![Some(5), Some(6), None]);
+        assert_eq!(snapshots[0].token_address, Felt::from_hex(ETH).unwrap());
+        assert_eq!(snapshots[0].quotes[1].0, Felt::TWO.pow(54_u8));
+    }
+
+    #[test]
+    fn replay_takes_the_most_profitable_allowed_route() {
+        let report = replay_fixture("two-hops", setting(0, 2), None);
+        assert_eq!((report.snapshots, report.quotes), (3, 4));
+        let [trade] = report.trades.as_slice() else {
+            panic!("expected a single trade");
+        };
+        assert_eq!(trade.block, Some(5));
+        assert_eq!(trade.amount, TokenAmount::from(1_u128 << 53));
+        assert_eq!(trade.profit, TokenAmount::from(10 * FEE));
+        assert_eq!(trade.fee, Felt::from(FEE));
+        assert_eq!(report.pnl, Felt::from(9 * FEE));
+        assert_eq!(format!("{:.3}", report.hit_rate()), "0.333");
+
+        let report = replay_fixture("three-hops", setting(0, 3), None);
+        let blocks: Vec<Option<u64>> = report.trades.iter().map(|trade| trade.block).collect();
+        assert_eq!(blocks, vec![Some(5), Some(6)]);
+        assert_eq!(report.pnl, Felt::from(13 * FEE));
+    }
+
+    #[test]
+    fn replay_skips_amounts_out_of_the_setting_and_balance() {
+        // 2^54 doesn't cover the fee limit
+        let report = replay_fixture("min-power", setting(54, 3), None);
+        assert_eq!(report.quotes, 2);
+        let amounts: Vec<TokenAmount> = report.trades.iter().map(|trade| trade.amount).collect();
+        assert_eq!(amounts, vec![TokenAmount::from(1_u128 << 55)]);
+
+        let report = replay_fixture("balance", setting(0, 3), Some(Felt::from(1_u128 << 54)));
+        assert_eq!(report.quotes, 3);
+        let blocks: Vec<Option<u64>> = report.trades.iter().map(|trade| trade.block).collect();
+        assert_eq!(blocks, vec![Some(5)]);
+    }
+
+    #[test]
+    fn trades_list_shows_unknown_blocks() {
+        let report = replay_fixture("trades", setting(0, 2), None);
+        let route_id = report.trades[0].route_id;
+        assert_eq!(
+            trade_line(&report.trades[0]),
+            format!("block 5 amount 9007199254740992 route {route_id:#x} profit 10000000000000000 fee 1000000000000000")
+        );
+        let trade = Trade {
+            block: None,
+            ..report.trades.into_iter().next().unwrap()
+        };
+        assert!(trade_line(&trade).starts_with("block ? amount 9007199254740992 "));
+    }
+
+    #[test]
+    fn sweep_ranks_the_grid_by_pnl_then_quotes() {
+        let args = SweepArgs {
+            replay: replay_args(fixture("sweep")),
+            min_profit: TokenAmount::zero(),
+            min_power_of_2: vec![53, 54],
+            max_power_of_2: vec![54, 65],
+            max_splits: vec![0],
+            max_hops: vec![2, 3],
+            num_top_quotes: vec![5],
+            fee_multiplier: vec![2.0],
+            csv: None,
+        };
+        let reports = sweep_reports(&args).unwrap();
+        // empty ranges (54..54) are skipped
+        let ranked: Vec<(u8, u8, u8, Felt, usize)> = reports
+            .iter()
+            .map(|report| {
+                let setting = &report.setting;
+                (
+                    setting.min_power_of_2,
+                    setting.max_power_of_2,
+                    setting.max_hops,
+                    report.pnl,
+                    report.quotes,
+                )
+            })
+            .collect();
+        assert_eq!(
+            ranked,
+            vec![
+                (53, 65, 3, Felt::from(13 * FEE), 4),
+                (53, 54, 2, Felt::from(9 * FEE), 2),
+                (53, 54, 3, Felt::from(9 * FEE), 2),
+                (53, 65, 2, Felt::from(9 * FEE), 4),
+                (54, 65, 3, Felt::from(4 * FEE), 2),
+                (54, 65, 2, Felt::ZERO, 2),
+            ]
+        );
+
+        assert_eq!(
+            csv(&reports[..2]),
+            "min_profit,min_power_of_2,max_power_of_2,max_splits,max_hops,num_top_quotes,fee_multiplier,snapshots,quotes,trades,hit_rate,pnl\n\
+             0,53,65,0,3,5,2,3,4,2,0.667,13000000000000000\n\
+             0,53,54,0,2,5,2,3,2,1,0.333,9000000000000000\n"
+        );
+    }
+
+    #[test]
+    fn csv_is_written_to_the_path() {
+        let dir = fixture("csv");
+        let reports = vec![replay_fixture("csv-report", setting(0, 2), None)];
+        let path = dir.join("ranked.csv");
+        write_csv(&reports, &path).unwrap();
+        assert_eq!(fs::read_to_string(path).unwrap(), csv(&reports));
+    }
+}
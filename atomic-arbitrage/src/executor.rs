@@ -0,0 +1,110 @@
+use crate::{get_account_balance, signer::AccountSigner};
+use color_eyre::eyre::{Result, WrapErr};
+use starknet::{
+    accounts::{Account, Call, ConnectedAccount, ExecutionEncoding, SingleOwnerAccount},
+    core::types::{BlockId, BlockTag, Felt, U256},
+    providers::{
+        jsonrpc::{HttpTransport, JsonRpcClient},
+        Url,
+    },
+    signers::Signer,
+};
+use std::env;
+use tracing::info;
+
+pub type ExecutorAccount = SingleOwnerAccount<JsonRpcClient<HttpTransport>, AccountSigner>;
+
+/// An account sending arbitrage transactions with its own nonce and balance
+pub struct Executor {
+    pub account: ExecutorAccount,
+    /// the balance of the arbitraged token
+    pub balance: U256,
+    nonce: Option<Felt>,
+}
+
+impl Executor {
+    /// `suffix` is appended to env variables of the account (`ACCOUNT_ADDRESS` and the signer)
+    pub async fn from_env(suffix: &str, provider_url: &Url, chain_id: Felt) -> Result<Self> {
+        let signer = AccountSigner::from_env(suffix)?;
+        let address = Felt::from_hex(&env::var(format!("ACCOUNT_ADDRESS{suffix}"))?)?;
+        info!(
+            "executor account {address:#x}, signer public key: {:#x}",
+            signer.get_public_key().await?.scalar()
+        );
+        let mut account = SingleOwnerAccount::new(
+            JsonRpcClient::new(HttpTransport::new(provider_url.clone())),
+            signer,
+            address,
+            chain_id,
+            ExecutionEncoding::New, // https://docs.rs/starknet/0.11.0/starknet/accounts/enum.ExecutionEncoding.html#variant.New,
+        );
+        // otherwise we will get a DuplicateTx error as nonce by default set to the latest block
+        account.set_block_id(BlockId::Tag(BlockTag::Pending));
+        Ok(Self {
+            account,
+            balance: U256::from(0u8),
+            nonce: None,
+        })
+    }
+
+    pub fn address(&self) -> Felt {
+        self.account.address()
+    }
+
+    pub async fn refresh_balance(&mut self, token_address: Felt) -> Result<U256> {
+        self.balance =
+            get_account_balance(token_address, self.address(), self.account.provider()).await?;
+        Ok(self.balance)
+    }
+
+    /// The nonce for the next transaction, it is fetched from the pending block only if unknown
+    pub async fn nonce(&mut self) -> Result<Felt> {
+        match self.nonce {
+            Some(nonce) => Ok(nonce),
+            None => {
+                let nonce =
+                    self.account.get_nonce().await.wrap_err_with(|| {
+                        format!("failed to get nonce of {:#x}", self.address())
+                    })?;
+                self.nonce = Some(nonce);
+                Ok(nonce)
+            }
+        }
+    }
+
+    /// Sends a transaction with the automatic fee estimation, the receipt is awaited by the caller
+    pub async fn broadcast(&mut self, calls: Vec<Call>) -> Result<Felt> {
+        let nonce = self.nonce().await?;
+        let tx = match self.account.execute_v1(calls).nonce(nonce).send().await {
+            Ok(tx) => tx,
+            Err(e) => {
+                self.reset_nonce();
+                return Err(e).wrap_err_with(|| {
+                    format!("Error while sending transaction from {:#x}", self.address())
+                });
+            }
+        };
+        self.sent(nonce);
+        Ok(tx.transaction_hash)
+    }
+
+    /// The transaction with the nonce is accepted by the node
+    pub fn sent(&mut self, nonce: Felt) {
+        self.nonce = Some(nonce + Felt::ONE);
+    }
+
+    /// The nonce is refetched before the next transaction
+    pub fn reset_nonce(&mut self) {
+        self.nonce = None;
+    }
+}
+
+/// Env suffixes of executor accounts: `_1`..`_N` if `EXECUTOR_ACCOUNTS=N`, otherwise a single account without a suffix
+pub fn executor_suffixes() -> Result<Vec<String>> {
+    match env::var("EXECUTOR_ACCOUNTS") {
+        Ok(accounts) => Ok((1..=accounts.parse::<usize>()?)
+            .map(|i| format!("_{i}"))
+            .collect()),
+        Err(_) => Ok(vec![String::new()]),
+    }
+}
@@ -0,0 +1,223 @@
+use crate::felt_to_u128;
+use color_eyre::eyre::{bail, ensure, Result};
+use ekubo::TokenAmount;
+use starknet::core::types::Felt;
+use std::collections::VecDeque;
+use std::env;
+
+// the adaptive limit is above the worst recent actual fee by the margin
+const ADAPTIVE_MARGIN: f64 = 0.1;
+
+/// How much the bot is ready to pay over the estimated fee, selected by `FEE_POLICY`:
+/// * `fixed` (default) - the estimate times `FEE_MULTIPLIER` (2 by default)
+/// * `profit-share` - the same but at most `FEE_MAX_PROFIT_SHARE_BPS` of the expected profit
+/// * `adaptive` - the estimate times the worst ratio of the actual fee to the estimate
+///   over the last `FEE_ADAPTIVE_WINDOW` receipts plus a margin, `FEE_MULTIPLIER` at most
+pub enum FeePolicy {
+    Fixed {
+        multiplier: f64,
+    },
+    ProfitShare {
+        multiplier: f64,
+        max_share_bps: u16,
+    },
+    Adaptive {
+        max_multiplier: f64,
+        window: usize,
+        ratios: VecDeque<f64>,
+    },
+}
+
+/// The max fee of a transaction and why it is chosen
+pub struct FeeLimit {
+    pub limit: Felt,
+    pub reason: String,
+}
+
+impl FeePolicy {
+    pub fn from_env() -> Result<Self> {
+        let multiplier: f64 = env::var("FEE_MULTIPLIER").map_or(Ok(2.0), |m| m.parse())?;
+        ensure!(multiplier >= 1.0, "FEE_MULTIPLIER should be at least 1");
+        let kind = env::var("FEE_POLICY").unwrap_or_else(|_| "fixed".to_string());
+        match kind.as_str() {
+            "fixed" => Ok(Self::Fixed { multiplier }),
+            "profit-share" => {
+                let max_share_bps: u16 = env::var("FEE_MAX_PROFIT_SHARE_BPS")?.parse()?;
+                ensure!(
+                    max_share_bps <= 10_000,
+                    "FEE_MAX_PROFIT_SHARE_BPS should be at most 10000"
+                );
+                Ok(Self::ProfitShare {
+                    multiplier,
+                    max_share_bps,
+                })
+            }
+            "adaptive" => Ok(Self::Adaptive {
+                max_multiplier: multiplier,
+                window: env::var("FEE_ADAPTIVE_WINDOW").map_or(Ok(20), |w| w.parse())?,
+                ratios: VecDeque::new(),
+            }),
+            _ => bail!(
+                "unsupported fee policy {kind}, expected one of: fixed, profit-share, adaptive"
+            ),
+        }
+    }
+
+    /// A limit below the estimate means that the transaction is not worth sending
+    pub fn limit(&self, estimated: Felt, profit: TokenAmount) -> FeeLimit {
+        match self {
+            Self::Fixed { multiplier } => FeeLimit {
+                limit: multiply(estimated, *multiplier),
+                reason: format!("fixed multiplier {multiplier}"),
+            },
+            Self::ProfitShare {
+                multiplier,
+                max_share_bps,
+            } => {
+                let by_multiplier = multiply(estimated, *multiplier);
+                let by_profit =
+                    Felt::from(profit.to_biguint() * u32::from(*max_share_bps) / 10_000u32);
+                if by_profit < by_multiplier {
+                    FeeLimit {
+                        limit: by_profit,
+                        reason: format!("{max_share_bps} bps of the expected profit"),
+                    }
+                } else {
+                    FeeLimit {
+                        limit: by_multiplier,
+                        reason: format!("multiplier {multiplier} within the profit share"),
+                    }
+                }
+            }
+            Self::Adaptive {
+                max_multiplier,
+                ratios,
+                ..
+            } => {
+                let Some(worst) = ratios.iter().copied().reduce(f64::max) else {
+                    return FeeLimit {
+                        limit: multiply(estimated, *max_multiplier),
+                        reason: format!("no recent receipts, multiplier {max_multiplier}"),
+                    };
+                };
+                let multiplier = (worst * (1.0 + ADAPTIVE_MARGIN)).clamp(1.0, *max_multiplier);
+                FeeLimit {
+                    limit: multiply(estimated, multiplier),
+                    reason: format!(
+                        "multiplier {multiplier:.3} from the worst actual to estimated fee ratio {worst:.3} of {} receipts",
+                        ratios.len()
+                    ),
+                }
+            }
+        }
+    }
+
+    /// The actual fee from a receipt of a transaction with the estimated fee
+    pub fn record(&mut self, estimated: Felt, actual: Felt) {
+        let Self::Adaptive { window, ratios, .. } = self else {
+            return;
+        };
+        let (Ok(estimated), Ok(actual)) = (felt_to_u128(estimated), felt_to_u128(actual)) else {
+            return;
+        };
+        if estimated == 0 {
+            return;
+        }
+        ratios.push_back(actual as f64 / estimated as f64);
+        while ratios.len() > *window {
+            ratios.pop_front();
+        }
+    }
+}
+
+// Felt division is in the field, so the fee is scaled as an integer
+pub fn multiply(fee: Felt, multiplier: f64) -> Felt {
+    let per_mille = (multiplier * 1000.0).round() as u64;
+    Felt::from(fee.to_biguint() * per_mille / 1000u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn adaptive(max_multiplier: f64, window: usize) -> FeePolicy {
+        FeePolicy::Adaptive {
+            max_multiplier,
+            window,
+            ratios: VecDeque::new(),
+        }
+    }
+
+    #[test]
+    fn multiplies_as_an_integer() {
+        assert_eq!(multiply(Felt::from(1000u32), 1.5), Felt::from(1500u32));
+        assert_eq!(multiply(Felt::from(1000u32), 1.0), Felt::from(1000u32));
+        // per mille precision, the rest is rounded
+        assert_eq!(multiply(Felt::from(1000u32), 1.2345), Felt::from(1235u32));
+        let large = Felt::from(u128::MAX);
+        assert_eq!(multiply(large, 2.0), large + large);
+    }
+
+    #[test]
+    fn fixed_limit() {
+        let policy = FeePolicy::Fixed { multiplier: 2.0 };
+        let fee = policy.limit(Felt::from(100u8), TokenAmount::zero());
+        assert_eq!(fee.limit, Felt::from(200u8));
+    }
+
+    #[test]
+    fn profit_share_limit() {
+        let policy = FeePolicy::ProfitShare {
+            multiplier: 2.0,
+            max_share_bps: 5_000,
+        };
+        // half of the profit is above the doubled estimate
+        let fee = policy.limit(Felt::from(100u8), TokenAmount::from(1_000u128));
+        assert_eq!(fee.limit, Felt::from(200u8));
+        // half of the profit is below, the limit is below the estimate, so it is not worth sending
+        let fee = policy.limit(Felt::from(100u8), TokenAmount::from(150u128));
+        assert_eq!(fee.limit, Felt::from(75u8));
+        assert!(fee.reason.contains("5000 bps"));
+    }
+
+    #[test]
+    fn adaptive_limit_follows_the_worst_recent_ratio() {
+        let mut policy = adaptive(3.0, 2);
+        let estimated = Felt::from(1_000u32);
+        // no receipts yet
+        assert_eq!(
+            policy.limit(estimated, TokenAmount::zero()).limit,
+            Felt::from(3_000u32)
+        );
+        policy.record(Felt::from(100u8), Felt::from(150u8));
+        policy.record(Felt::from(100u8), Felt::from(50u8));
+        // 1.5 plus the margin
+        assert_eq!(
+            policy.limit(estimated, TokenAmount::zero()).limit,
+            Felt::from(1_650u32)
+        );
+        // the ratio of 1.5 leaves the window, 0.5 is raised to the estimate
+        policy.record(Felt::from(100u8), Felt::from(60u8));
+        assert_eq!(
+            policy.limit(estimated, TokenAmount::zero()).limit,
+            estimated
+        );
+        // capped by the max multiplier
+        policy.record(Felt::from(100u8), Felt::from(1_000u32));
+        assert_eq!(
+            policy.limit(estimated, TokenAmount::zero()).limit,
+            Felt::from(3_000u32)
+        );
+    }
+
+    #[test]
+    fn adaptive_skips_unusable_receipts() {
+        let mut policy = adaptive(3.0, 20);
+        policy.record(Felt::ZERO, Felt::from(100u8));
+        policy.record(Felt::from(100u8), Felt::from(u128::MAX) + Felt::ONE);
+        let FeePolicy::Adaptive { ratios, .. } = &policy else {
+            unreachable!()
+        };
+        assert!(ratios.is_empty());
+    }
+}
@@ -0,0 +1,446 @@
+use crate::felt_to_u128;
+use color_eyre::eyre::{bail, eyre, Result, WrapErr};
+use ekubo::models::PoolKey;
+use starknet::{
+    core::{
+        types::{
+            BlockId, EmittedEvent, EventFilter, Felt, MaybePendingBlockWithTxHashes, StarknetError,
+            U256,
+        },
+        utils::get_selector_from_name,
+    },
+    providers::{
+        jsonrpc::{HttpTransport, JsonRpcClient},
+        Provider, ProviderError,
+    },
+};
+use std::collections::{HashMap, HashSet, VecDeque};
+use tracing::{debug, info, warn};
+
+const EVENTS_CHUNK_SIZE: u64 = 1000;
+// Starknet reorgs are shallow, we only keep undo logs for the most recent blocks
+const MAX_REORG_DEPTH: usize = 64;
+
+/// The latest known state of an Ekubo pool
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolState {
+    pub sqrt_ratio: U256,
+    pub tick: i128,
+    pub liquidity: u128,
+    /// the block of the last event which changed the state
+    pub block_number: u64,
+}
+
+/// `Swapped` event of Ekubo Core
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Swap {
+    /// the contract which has locked Core to swap (a router or an extension)
+    pub locker: Felt,
+    pub pool_key: PoolKey,
+    pub sqrt_ratio_after: U256,
+    pub tick_after: i128,
+    pub liquidity_after: u128,
+}
+
+impl Swap {
+    pub fn from_event_data(data: &[Felt]) -> Result<Self> {
+        // locker, pool_key (5), params (6), delta (4), sqrt_ratio_after (2), tick_after (2), liquidity_after
+        ensure_len(data, 21, "Swapped")?;
+        Ok(Self {
+            locker: data[0],
+            pool_key: pool_key_from(&data[1..6])?,
+            sqrt_ratio_after: U256::from_words(felt_to_u128(data[16])?, felt_to_u128(data[17])?),
+            tick_after: i129_to_i128(data[18], data[19])?,
+            liquidity_after: felt_to_u128(data[20])?,
+        })
+    }
+}
+
+struct IndexedBlock {
+    number: u64,
+    hash: Felt,
+    // the state of every touched pool before the block was applied
+    // (`None` if the pool was unknown)
+    undo: Vec<(PoolKey, Option<PoolState>)>,
+}
+
+/// Follows `Swapped` and `PositionUpdated` events of Ekubo Core
+/// and keeps an in-memory map of pool states.
+///
+/// A pool is added to the map on its first swap as the `Swapped` event carries
+/// the full post-swap state (sqrt ratio, tick and liquidity). `PositionUpdated`
+/// only adjusts the liquidity of already known pools.
+pub struct Indexer {
+    core_address: Felt,
+    swapped_selector: Felt,
+    position_updated_selector: Felt,
+    pools: HashMap<PoolKey, PoolState>,
+    history: VecDeque<IndexedBlock>,
+    next_block: u64,
+}
+
+impl Indexer {
+    pub fn new(core_address: Felt, start_block: u64) -> Result<Self> {
+        Ok(Self {
+            core_address,
+            swapped_selector: get_selector_from_name("Swapped")?,
+            position_updated_selector: get_selector_from_name("PositionUpdated")?,
+            pools: HashMap::new(),
+            history: VecDeque::new(),
+            next_block: start_block,
+        })
+    }
+
+    pub fn pool(&self, pool_key: &PoolKey) -> Option<&PoolState> {
+        self.pools.get(pool_key)
+    }
+
+    /// Catches up with the chain head and returns pools moved by swaps since the last sync
+    pub async fn sync(
+        &mut self,
+        provider: &JsonRpcClient<HttpTransport>,
+    ) -> Result<HashSet<PoolKey>> {
+        self.rollback_reorged(provider).await?;
+        let head = provider
+            .block_hash_and_number()
+            .await
+            .wrap_err("failed to get the latest block")?;
+        let mut moved = HashSet::new();
+        if head.block_number < self.next_block {
+            return Ok(moved);
+        }
+        for event in self
+            .fetch_events(provider, self.next_block, head.block_number)
+            .await?
+        {
+            let (Some(number), Some(hash)) = (event.block_number, event.block_hash) else {
+                // we only request accepted blocks
+                continue;
+            };
+            if self.history.back().map(|block| block.number) != Some(number) {
+                self.push_block(number, hash);
+            }
+            if let Some(pool_key) = self.apply(number, &event)? {
+                moved.insert(pool_key);
+            }
+        }
+        if self.history.back().map(|block| block.number) != Some(head.block_number) {
+            self.push_block(head.block_number, head.block_hash);
+        }
+        self.next_block = head.block_number + 1;
+        debug!(
+            "indexed up to block {}, {} pools known",
+            head.block_number,
+            self.pools.len()
+        );
+        Ok(moved)
+    }
+
+    fn push_block(&mut self, number: u64, hash: Felt) {
+        self.history.push_back(IndexedBlock {
+            number,
+            hash,
+            undo: vec![],
+        });
+        if self.history.len() > MAX_REORG_DEPTH {
+            self.history.pop_front();
+        }
+    }
+
+    // Walks back the indexed blocks until the one which is still on the canonical chain
+    // and restores pool states from the undo logs
+    async fn rollback_reorged(&mut self, provider: &JsonRpcClient<HttpTransport>) -> Result<()> {
+        while let Some(block) = self.history.back() {
+            if block_hash(provider, block.number).await? == Some(block.hash) {
+                return Ok(());
+            }
+            warn!(
+                "reorg detected: block {} {:#x} is not canonical anymore",
+                block.number, block.hash
+            );
+            self.undo_last_block();
+            if self.history.is_empty() {
+                warn!("reorg is deeper than {MAX_REORG_DEPTH} blocks, pool states may be stale");
+            }
+        }
+        Ok(())
+    }
+
+    // History keeps only blocks with events and synced heads,
+    // so blocks after the previous entry are fetched again as they may have got events by the reorg
+    fn undo_last_block(&mut self) {
+        let Some(block) = self.history.pop_back() else {
+            return;
+        };
+        for (pool_key, state) in block.undo.into_iter().rev() {
+            match state {
+                Some(state) => self.pools.insert(pool_key, state),
+                None => self.pools.remove(&pool_key),
+            };
+        }
+        self.next_block = self
+            .history
+            .back()
+            .map_or(block.number, |previous| previous.number + 1);
+    }
+
+    async fn fetch_events(
+        &self,
+        provider: &JsonRpcClient<HttpTransport>,
+        from_block: u64,
+        to_block: u64,
+    ) -> Result<Vec<EmittedEvent>> {
+        let filter = EventFilter {
+            from_block: Some(BlockId::Number(from_block)),
+            to_block: Some(BlockId::Number(to_block)),
+            address: Some(self.core_address),
+            keys: Some(vec![vec![
+                self.swapped_selector,
+                self.position_updated_selector,
+            ]]),
+        };
+        let mut events = vec![];
+        let mut continuation_token = None;
+        loop {
+            let page = provider
+                .get_events(filter.clone(), continuation_token, EVENTS_CHUNK_SIZE)
+                .await
+                .wrap_err("failed to get Ekubo Core events")?;
+            events.extend(page.events);
+            continuation_token = page.continuation_token;
+            if continuation_token.is_none() {
+                return Ok(events);
+            }
+        }
+    }
+
+    // Returns the pool key if the event is a swap
+    fn apply(&mut self, block_number: u64, event: &EmittedEvent) -> Result<Option<PoolKey>> {
+        let data = &event.data;
+        let selector = event.keys.first().copied();
+        if selector == Some(self.swapped_selector) {
+            let swap = Swap::from_event_data(data)?;
+            let state = PoolState {
+                sqrt_ratio: swap.sqrt_ratio_after,
+                tick: swap.tick_after,
+                liquidity: swap.liquidity_after,
+                block_number,
+            };
+            self.update(swap.pool_key.clone(), state);
+            Ok(Some(swap.pool_key))
+        } else if selector == Some(self.position_updated_selector) {
+            // locker, pool_key (5), salt, bounds (4), liquidity_delta (2), delta (4)
+            ensure_len(data, 17, "PositionUpdated")?;
+            let pool_key = pool_key_from(&data[1..6])?;
+            let Some(mut state) = self.pools.get(&pool_key).copied() else {
+                return Ok(None);
+            };
+            let lower = i129_to_i128(data[7], data[8])?;
+            let upper = i129_to_i128(data[9], data[10])?;
+            // only positions containing the current tick contribute to the active liquidity
+            if lower <= state.tick && state.tick < upper {
+                let delta = felt_to_u128(data[11])?;
+                state.liquidity = if data[12] == Felt::ZERO {
+                    state.liquidity.checked_add(delta)
+                } else {
+                    state.liquidity.checked_sub(delta)
+                }
+                .ok_or_else(|| eyre!("liquidity overflow for pool {:#x}", pool_key.pool_id()))?;
+                state.block_number = block_number;
+                self.update(pool_key, state);
+            }
+            Ok(None)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn update(&mut self, pool_key: PoolKey, state: PoolState) {
+        let previous = self.pools.insert(pool_key.clone(), state);
+        if let Some(block) = self.history.back_mut() {
+            if !block.undo.iter().any(|(key, _)| *key == pool_key) {
+                block.undo.push((pool_key, previous));
+            }
+        }
+    }
+}
+
+/// Returns the number of the latest accepted block to start indexing from
+pub async fn latest_block(provider: &JsonRpcClient<HttpTransport>) -> Result<u64> {
+    let number = provider
+        .block_number()
+        .await
+        .wrap_err("failed to get the latest block number")?;
+    info!("indexing Ekubo Core events from block {number}");
+    Ok(number)
+}
+
+async fn block_hash(provider: &JsonRpcClient<HttpTransport>, number: u64) -> Result<Option<Felt>> {
+    match provider
+        .get_block_with_tx_hashes(BlockId::Number(number))
+        .await
+    {
+        Ok(MaybePendingBlockWithTxHashes::Block(block)) => Ok(Some(block.block_hash)),
+        Ok(MaybePendingBlockWithTxHashes::PendingBlock(_)) => Ok(None),
+        // the block has been removed by a reorg
+        Err(ProviderError::StarknetError(StarknetError::BlockNotFound)) => Ok(None),
+        Err(e) => Err(e).wrap_err_with(|| format!("failed to get block {number}")),
+    }
+}
+
+fn ensure_len(data: &[Felt], len: usize, event: &str) -> Result<()> {
+    if data.len() != len {
+        bail!("unexpected {event} event data length {}", data.len());
+    }
+    Ok(())
+}
+
+// PoolKey in the ABI
+fn pool_key_from(felts: &[Felt]) -> Result<PoolKey> {
+    let [token0, token1, fee, tick_spacing, extension] = felts[..] else {
+        bail!("pool key should consist of 5 felts");
+    };
+    Ok(PoolKey {
+        token0,
+        token1,
+        fee,
+        tick_spacing: i32::try_from(felt_to_u128(tick_spacing)?)?,
+        extension,
+    })
+}
+
+// i129 in the ABI is a pair of a magnitude and a sign
+fn i129_to_i128(mag: Felt, sign: Felt) -> Result<i128> {
+    let mag = i128::try_from(felt_to_u128(mag)?)?;
+    Ok(if sign == Felt::ZERO { mag } else { -mag })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CORE: Felt = Felt::from_hex_unchecked("0xc0de");
+
+    fn pool_key(token1: u8) -> PoolKey {
+        PoolKey::new(Felt::ONE, Felt::from(token1), Felt::ZERO, 1000, Felt::ZERO)
+    }
+
+    fn event(name: &str, block_number: u64, data: Vec<Felt>) -> EmittedEvent {
+        EmittedEvent {
+            from_address: CORE,
+            keys: vec![get_selector_from_name(name).unwrap()],
+            data,
+            block_hash: Some(Felt::from(block_number)),
+            block_number: Some(block_number),
+            transaction_hash: Felt::ZERO,
+        }
+    }
+
+    fn pool_key_data(pool_key: &PoolKey) -> Vec<Felt> {
+        vec![
+            pool_key.token0,
+            pool_key.token1,
+            pool_key.fee,
+            Felt::from(pool_key.tick_spacing),
+            pool_key.extension,
+        ]
+    }
+
+    fn swapped(block_number: u64, pool_key: &PoolKey, tick: u8) -> EmittedEvent {
+        let mut data = vec![Felt::ZERO];
+        data.extend(pool_key_data(pool_key));
+        // params and delta
+        data.extend([Felt::ZERO; 10]);
+        // sqrt_ratio_after, tick_after, liquidity_after
+        data.extend([Felt::from(tick), Felt::ZERO, Felt::from(tick), Felt::ZERO]);
+        data.push(Felt::from(1_000u32));
+        event("Swapped", block_number, data)
+    }
+
+    fn position_updated(block_number: u64, pool_key: &PoolKey, delta: u32) -> EmittedEvent {
+        let mut data = vec![Felt::ZERO];
+        data.extend(pool_key_data(pool_key));
+        // salt, bounds [-100, 100], liquidity_delta, delta
+        data.extend([
+            Felt::ZERO,
+            Felt::from(100u8),
+            Felt::ONE,
+            Felt::from(100u8),
+            Felt::ZERO,
+        ]);
+        data.extend([Felt::from(delta), Felt::ZERO]);
+        data.extend([Felt::ZERO; 4]);
+        event("PositionUpdated", block_number, data)
+    }
+
+    fn apply(indexer: &mut Indexer, event: EmittedEvent) {
+        let number = event.block_number.unwrap();
+        if indexer.history.back().map(|block| block.number) != Some(number) {
+            indexer.push_block(number, event.block_hash.unwrap());
+        }
+        indexer.apply(number, &event).unwrap();
+    }
+
+    fn tick(indexer: &Indexer, pool_key: &PoolKey) -> Option<i128> {
+        indexer.pool(pool_key).map(|state| state.tick)
+    }
+
+    #[test]
+    fn applies_swaps_and_positions() {
+        let mut indexer = Indexer::new(CORE, 100).unwrap();
+        let (a, b) = (pool_key(2), pool_key(3));
+        // positions of unknown pools are skipped
+        apply(&mut indexer, position_updated(100, &a, 5));
+        assert_eq!(indexer.pool(&a), None);
+        apply(&mut indexer, swapped(100, &a, 7));
+        apply(&mut indexer, position_updated(101, &a, 5));
+        let state = indexer.pool(&a).unwrap();
+        assert_eq!(
+            (state.tick, state.liquidity, state.block_number),
+            (7, 1_005, 101)
+        );
+        assert_eq!(indexer.pool(&b), None);
+    }
+
+    #[test]
+    fn undo_restores_pool_states() {
+        let mut indexer = Indexer::new(CORE, 100).unwrap();
+        let (a, b) = (pool_key(2), pool_key(3));
+        apply(&mut indexer, swapped(100, &a, 1));
+        apply(&mut indexer, swapped(105, &a, 2));
+        apply(&mut indexer, swapped(105, &a, 3));
+        apply(&mut indexer, swapped(105, &b, 4));
+        apply(&mut indexer, position_updated(105, &a, 5));
+        // the synced head
+        indexer.push_block(110, Felt::from(110u8));
+        indexer.next_block = 111;
+
+        indexer.undo_last_block();
+        assert_eq!(indexer.next_block, 106);
+        assert_eq!(tick(&indexer, &a), Some(3));
+
+        // the state before the block, the pool first seen in the block is forgotten
+        indexer.undo_last_block();
+        assert_eq!(tick(&indexer, &a), Some(1));
+        assert_eq!(indexer.pool(&a).unwrap().liquidity, 1_000);
+        assert_eq!(indexer.pool(&b), None);
+    }
+
+    #[test]
+    fn blocks_after_the_canonical_entry_are_fetched_again() {
+        let mut indexer = Indexer::new(CORE, 100).unwrap();
+        let a = pool_key(2);
+        apply(&mut indexer, swapped(100, &a, 1));
+        apply(&mut indexer, swapped(120, &a, 2));
+        indexer.next_block = 121;
+        // block 120 is reorged, blocks 101..120 may have got events
+        indexer.undo_last_block();
+        assert_eq!(indexer.next_block, 101);
+        // deeper than the history
+        indexer.undo_last_block();
+        assert_eq!(indexer.next_block, 100);
+        assert_eq!(indexer.pool(&a), None);
+        indexer.undo_last_block();
+        assert_eq!(indexer.next_block, 100);
+    }
+}
@@ -1,17 +1,37 @@
-use clap::{Parser, ValueEnum};
-use color_eyre::eyre::{bail, ensure, eyre, Result};
+mod backtest;
+mod executor;
+mod fee;
+mod indexer;
+mod notifier;
+mod pools;
+mod risk;
+mod signer;
+mod simulation;
+mod telemetry;
+mod treasury;
+mod trigger;
+
+use backtest::Snapshot;
+use clap::{Parser, Subcommand, ValueEnum};
+use color_eyre::eyre::{bail, ensure, eyre, Report, Result, WrapErr};
 use ekubo::{
     models::{PoolKey, Quote, Quotes, RouteNode},
-    Client,
+    Client, TokenAmount, TokenInfo, TokenRegistry,
 };
+use executor::Executor;
+use fee::{FeeLimit, FeePolicy};
 use futures::future::join_all;
+use indexer::{Indexer, Swap};
+use notifier::{Event, Notifier};
+use pools::PoolFilter;
+use risk::{Risk, TradeOutcome};
 use starknet::{
-    accounts::{Account, Call, ConnectedAccount, ExecutionEncoding, SingleOwnerAccount},
+    accounts::{Account, Call, ConnectedAccount},
     core::{
         chain_id,
         types::{
-            BlockId, BlockTag, Felt, FunctionCall, TransactionReceiptWithBlockInfo,
-            TransactionStatus, U256,
+            BlockId, BlockTag, ExecutionResult, Felt, FunctionCall, TransactionReceipt,
+            TransactionReceiptWithBlockInfo, TransactionStatus, U256,
         },
         utils::get_selector_from_name,
     },
@@ -19,30 +39,43 @@ use starknet::{
         jsonrpc::{HttpTransport, JsonRpcClient},
         Provider, Url,
     },
-    signers::{LocalWallet, SigningKey},
 };
 use std::cmp::Reverse;
 use std::collections::HashSet;
 use std::env;
 use std::iter;
 use std::path::PathBuf;
-use tokio::time::{sleep, Duration};
-use tracing::{debug, error, info};
-use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+use tokio::time::{timeout, Duration};
+use tracing::{debug, error, info, info_span, instrument, warn, Instrument};
+use treasury::Treasury;
+use trigger::Trigger;
 
+#[derive(Clone)]
 struct ArbitrageOpportunity {
-    amount: Felt,
+    amount: TokenAmount,
     quotes: Quotes,
-    profit: Felt,
+    profit: TokenAmount,
+    // the account and the fee estimated by it while ranking, the fee is reused for sending from the same account
+    estimated_fee: Option<(Felt, Felt)>,
 }
 
 impl ArbitrageOpportunity {
+    // the input plus the minimum profit plus the fee, `None` on u256 overflow
+    fn minimum_output(&self, min_profit: TokenAmount, fee: Felt) -> Option<TokenAmount> {
+        self.amount
+            .checked_add(min_profit)?
+            .checked_add(TokenAmount::from(fee))
+    }
+
     fn into_strategy(
         self,
         arbitrage_address: Felt,
         token_address: Felt,
         mode: Mode,
-    ) -> Option<(Felt, Felt, Vec<Call>)> {
+        minimum_output: TokenAmount,
+        account_address: Felt,
+        balance: TokenAmount,
+    ) -> Option<(TokenAmount, TokenAmount, Vec<Call>)> {
         let amount = self.amount;
         let profit = self.profit;
         let mut splits = self.quotes.splits;
@@ -71,66 +104,164 @@ impl ArbitrageOpportunity {
                 let transfer_call = Call {
                     to: token_address,
                     selector: get_selector_from_name("transfer").unwrap(),
-                    calldata: vec![arbitrage_address, amount, Felt::ZERO],
+                    calldata: vec![
+                        arbitrage_address,
+                        Felt::from(amount.low()),
+                        Felt::from(amount.high()),
+                    ],
                 };
 
-                // clear_minimum takes the second argument (amount) as U256
                 // withdraw the output
-                let clear_profits_call = Call {
-                    to: arbitrage_address,
-                    selector: get_selector_from_name("clear_minimum").unwrap(),
-                    calldata: vec![token_address, amount, Felt::ZERO],
-                };
+                let clear_profits_call =
+                    clear_minimum_call(arbitrage_address, token_address, minimum_output);
                 vec![transfer_call, call, clear_profits_call]
             }
+            // The flash loan contract reverts if it cannot repay the loan and pays the profit to the account,
+            // so the account balance should rise by the minimum output over the input
             Mode::EkuboFlash => {
-                vec![call]
+                let minimum_balance = balance.checked_add(minimum_output.checked_sub(amount)?)?;
+                vec![
+                    call,
+                    balance_check_call(token_address, account_address, minimum_balance),
+                ]
             }
         };
         Some((profit, amount, calls))
     }
+
+    // Replaces the extreme sqrt ratio limits from the API with the simulated post-swap price
+    // of every hop plus the tolerance, so a stale quote fails fast instead of swapping at a bad price
+    fn tighten_sqrt_ratio_limits(
+        &mut self,
+        swaps: &[Swap],
+        locker: Felt,
+        tolerance_bps: u16,
+    ) -> Result<()> {
+        // other swaps may be done by extensions (e.g. TWAMM virtual orders)
+        let mut swaps = swaps.iter().filter(|swap| swap.locker == locker);
+        for node in self
+            .quotes
+            .splits
+            .iter_mut()
+            .flat_map(|split| split.route.iter_mut())
+        {
+            let swap = swaps
+                .next()
+                .filter(|swap| swap.pool_key == node.pool_key)
+                .ok_or_else(|| eyre!("simulated swaps don't match the route"))?;
+            node.sqrt_ratio_limit = tightened_sqrt_ratio_limit(
+                node.sqrt_ratio_limit,
+                swap.sqrt_ratio_after,
+                tolerance_bps,
+            );
+        }
+        Ok(())
+    }
+}
+
+// The limit from the API shows the direction of the price move.
+// The tolerance is on the price, so it is halved for the sqrt ratio (the first order approximation)
+fn tightened_sqrt_ratio_limit(limit: Felt, sqrt_ratio_after: U256, tolerance_bps: u16) -> Felt {
+    let denominator = U256::from(20_000u32);
+    let tolerance = U256::from(tolerance_bps);
+    let tightened = if U256::from(limit) < sqrt_ratio_after {
+        sqrt_ratio_after * (denominator - tolerance) / denominator
+    } else {
+        sqrt_ratio_after * (denominator + tolerance) / denominator
+    };
+    let tightened = u256_to_felt(tightened);
+    // never looser than the API limit
+    if U256::from(limit) < sqrt_ratio_after {
+        limit.max(tightened)
+    } else {
+        limit.min(tightened)
+    }
+}
+
+// clear_minimum takes the second argument (minimum) as U256
+// It withdraws the whole balance of the token at the router and reverts if it is less than the minimum
+// So a stale quote reverts instead of withdrawing less than we paid
+fn clear_minimum_call(router_address: Felt, token_address: Felt, minimum: TokenAmount) -> Call {
+    Call {
+        to: router_address,
+        selector: get_selector_from_name("clear_minimum").unwrap(),
+        calldata: vec![
+            token_address,
+            Felt::from(minimum.low()),
+            Felt::from(minimum.high()),
+        ],
+    }
 }
 
-async fn check_arbitrage(
+// A transfer of the minimum balance from the account to itself doesn't move anything
+// but reverts if the balance is lower, as ERC20 checks the balance of the sender anyway
+fn balance_check_call(token_address: Felt, account_address: Felt, minimum: TokenAmount) -> Call {
+    Call {
+        to: token_address,
+        selector: get_selector_from_name("transfer").unwrap(),
+        calldata: vec![
+            account_address,
+            Felt::from(minimum.low()),
+            Felt::from(minimum.high()),
+        ],
+    }
+}
+
+#[instrument(name = "quote", skip_all, fields(%amount))]
+async fn fetch_quotes(
     client: &Client,
+    tokens: &TokenRegistry,
     amount: Felt,
-    min_profit: Felt,
-    token_address: &str,
+    token_address: Felt,
     max_splits: u8,
     max_hops: u8,
-    official_extensions: &HashSet<Felt>,
-) -> Option<ArbitrageOpportunity> {
+) -> Option<(Felt, Quotes)> {
     let quotes = client
-        .quotes(amount, token_address, token_address, max_splits, max_hops)
+        .quotes_by_address(amount, token_address, token_address, max_splits, max_hops)
         .await
         .map_err(|e| {
-            error!("quotes err: {e:#?}");
+            error!(error = %format_args!("{e:#}"), "quotes request failed");
             e
         })
         .ok()?;
-    debug!("quotes for amount {amount}:\n{quotes:#?}");
-    let total = quotes.total;
-    let extensions: HashSet<Felt> = quotes
+    debug!(
+        total = %quotes.total,
+        route_id = %route_id(&quotes),
+        route = %quotes.render(token_address, tokens),
+        "quotes"
+    );
+    Some((amount, quotes))
+}
+
+// Quotes of the token to itself are an opportunity if they return more than the amount plus the minimum profit
+fn check_arbitrage(
+    amount: Felt,
+    quotes: Quotes,
+    min_profit: TokenAmount,
+    pools: &PoolFilter,
+) -> Option<ArbitrageOpportunity> {
+    let amount = TokenAmount::from(amount);
+    // a loss is `None` instead of a wrapped huge profit
+    let profit = quotes.total.checked_sub(amount)?;
+    let allowed = quotes
         .splits
         .iter()
-        .map(|quote| quote.route.iter())
-        .flatten()
-        .filter_map(|node| {
-            let ext = node.pool_key.extension;
-            (ext != Felt::ZERO).then_some(ext)
-        })
-        .collect();
+        .flat_map(|quote| quote.route.iter())
+        .all(|node| pools.allows(&node.pool_key));
 
-    (extensions.is_subset(official_extensions)
-        && total > amount + min_profit
-        && !quotes.splits.is_empty())
-    .then(|| ArbitrageOpportunity {
+    (allowed && profit > min_profit && !quotes.splits.is_empty()).then_some(ArbitrageOpportunity {
         amount,
         quotes,
-        profit: total - amount,
+        profit,
+        estimated_fee: None,
     })
 }
 
+// An id to group logs of the same route, stable across restarts
+fn route_id(quotes: &Quotes) -> String {
+    format!("{:#x}", quotes.route_id())
+}
+
 // RouteNode in the ABI
 fn node_to_array(node: RouteNode) -> [Felt; 8] {
     let RouteNode {
@@ -187,6 +318,398 @@ fn call_data_for_multisplit(splits: Vec<Quote>, token_address: Felt) -> impl Ite
     )
 }
 
+// Independent opportunities (without common pools) are sent in parallel from different executors.
+// In the simple mode an executor should be able to pay the input amount.
+fn schedule(
+    opportunities: Vec<ArbitrageOpportunity>,
+    executors: &mut [Executor],
+    mode: Mode,
+) -> Vec<(&mut Executor, ArbitrageOpportunity)> {
+    let mut free: Vec<&mut Executor> = executors.iter_mut().collect();
+    let mut used_pools: HashSet<&PoolKey> = HashSet::new();
+    let mut scheduled = vec![];
+    for opportunity in &opportunities {
+        let pools: HashSet<&PoolKey> = opportunity
+            .quotes
+            .splits
+            .iter()
+            .flat_map(|split| split.route.iter().map(|node| &node.pool_key))
+            .collect();
+        if !pools.is_disjoint(&used_pools) {
+            continue;
+        }
+        let Some(i) = free.iter().position(|executor| {
+            mode != Mode::Simple || U256::from(opportunity.amount) <= executor.balance
+        }) else {
+            continue;
+        };
+        used_pools.extend(pools);
+        // the order of free executors is kept, so the first one gets the best opportunity
+        scheduled.push((free.remove(i), opportunity.clone()));
+        if free.is_empty() {
+            break;
+        }
+    }
+    scheduled
+}
+
+// Execution settings shared by all executors
+struct Settings {
+    mode: Mode,
+    arbitrage_address: Felt,
+    token_address: Felt,
+    // symbol and decimals for readable amounts in logs if the token is listed
+    token: Option<TokenInfo>,
+    // symbols of listed tokens in routes
+    tokens: TokenRegistry,
+    min_profit: TokenAmount,
+    slippage_tolerance_bps: Option<u16>,
+    explorer_url: String,
+    notifier: Notifier,
+}
+
+impl Settings {
+    // e.g. `0.0015 ETH` or WEI for an unlisted token
+    fn readable(&self, amount: TokenAmount) -> String {
+        match &self.token {
+            Some(token) => format!("{} {}", amount.format_units(token.decimals), token.symbol),
+            None => amount.to_string(),
+        }
+    }
+
+    fn calls(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        minimum_output: TokenAmount,
+        executor: &Executor,
+    ) -> Option<Vec<Call>> {
+        opportunity
+            .clone()
+            .into_strategy(
+                self.arbitrage_address,
+                self.token_address,
+                self.mode,
+                minimum_output,
+                executor.address(),
+                TokenAmount::from(executor.balance),
+            )
+            .map(|(_, _, calls)| calls)
+    }
+
+    async fn estimate_fee(
+        &self,
+        opportunity: &ArbitrageOpportunity,
+        executor: &Executor,
+    ) -> Option<Felt> {
+        let calls = self.calls(
+            opportunity,
+            opportunity.minimum_output(self.min_profit, Felt::ZERO)?,
+            executor,
+        )?;
+        executor
+            .account
+            .execute_v1(calls)
+            .estimate_fee()
+            .instrument(info_span!("estimate_fee"))
+            .await
+            .map_err(|e| {
+                error!(
+                    route_id = %route_id(&opportunity.quotes),
+                    error = %format_args!("{e:#}"),
+                    "fee estimation failed"
+                )
+            })
+            .ok()
+            .map(|estimate| estimate.overall_fee)
+    }
+
+    // Fees of the top opportunities are estimated concurrently and they are ranked by the profit minus the fee,
+    // as a large multi-split route may cost more gas than it earns over a smaller one.
+    // An opportunity which doesn't cover its fee or cannot be estimated is dropped.
+    async fn rank_by_net_profit(
+        &self,
+        candidates: Vec<ArbitrageOpportunity>,
+        executors: &[Executor],
+    ) -> Vec<ArbitrageOpportunity> {
+        let estimates = join_all(candidates.iter().map(|opportunity| async {
+            // the fee is estimated by the first executor which is able to pay the input,
+            // the one which `schedule` gives the opportunity to if it is free
+            let executor = executors.iter().find(|executor| {
+                self.mode != Mode::Simple || U256::from(opportunity.amount) <= executor.balance
+            })?;
+            let fee = self.estimate_fee(opportunity, executor).await?;
+            Some((executor.address(), fee))
+        }))
+        .await;
+        let mut ranked: Vec<(TokenAmount, ArbitrageOpportunity)> = candidates
+            .into_iter()
+            .zip(estimates)
+            .filter_map(|(mut opportunity, estimate)| {
+                let (account, fee) = estimate?;
+                debug!(
+                    route_id = %route_id(&opportunity.quotes),
+                    amount = %opportunity.amount,
+                    profit = %opportunity.profit,
+                    %fee,
+                    "estimated fee"
+                );
+                let net_profit = opportunity.profit.checked_sub(TokenAmount::from(fee))?;
+                opportunity.estimated_fee = Some((account, fee));
+                (!net_profit.is_zero()).then_some((net_profit, opportunity))
+            })
+            .collect();
+        ranked.sort_unstable_by_key(|(net_profit, _)| Reverse(*net_profit));
+        ranked
+            .into_iter()
+            .map(|(_, opportunity)| opportunity)
+            .collect()
+    }
+
+    #[instrument(
+        name = "execute",
+        skip_all,
+        fields(
+            account = %format_args!("{:#x}", executor.address()),
+            amount = %opportunity.amount,
+            profit = %opportunity.profit,
+            route_id = %route_id(&opportunity.quotes),
+        )
+    )]
+    async fn execute(
+        &self,
+        executor: &mut Executor,
+        mut opportunity: ArbitrageOpportunity,
+        fee_policy: &FeePolicy,
+    ) -> Result<Option<TradeOutcome>> {
+        let Settings {
+            arbitrage_address,
+            token_address,
+            min_profit,
+            slippage_tolerance_bps,
+            ref explorer_url,
+            ref notifier,
+            ..
+        } = *self;
+        let Some(calls) = opportunity
+            .minimum_output(min_profit, Felt::ZERO)
+            .and_then(|minimum_output| self.calls(&opportunity, minimum_output, executor))
+        else {
+            return Ok(None);
+        };
+        let nonce = executor.nonce().await?;
+        let account = &executor.account;
+        let (profit, amount) = (opportunity.profit, opportunity.amount);
+        info!(
+            readable_profit = %self.readable(profit),
+            route = %opportunity.quotes.render(token_address, &self.tokens),
+            "executing top arbitrage"
+        );
+        for call in &calls {
+            debug!(
+                to = %format_args!("{:#x}", call.to),
+                selector = %format_args!("{:#x}", call.selector),
+                calldata = %call
+                    .calldata
+                    .iter()
+                    .map(|felt| format!("{felt:#x}"))
+                    .collect::<Vec<_>>()
+                    .join(","),
+                "call"
+            );
+        }
+        // gas fees in WEI as we use tx v1,
+        // see https://docs.rs/starknet/0.11.0/starknet/core/types/struct.FeeEstimate.html
+        let total_gas_cost_wei = match opportunity.estimated_fee {
+            Some((account, fee)) if account == executor.address() => fee,
+            // calls depend on the account, so the fee is estimated again by the sending one
+            _ => match self.estimate_fee(&opportunity, executor).await {
+                Some(fee) => fee,
+                None => return Ok(None),
+            },
+        };
+        // Get a tx receipt, actual fee, link to the explorer with tx
+        let FeeLimit {
+            limit: limit_fee,
+            reason,
+        } = fee_policy.limit(total_gas_cost_wei, profit);
+        // the trade should pay for itself on-chain: the input, the minimum profit and the expected fee
+        let minimum_output = opportunity
+            .minimum_output(min_profit, total_gas_cost_wei)
+            .ok_or_else(|| eyre!("the minimum output overflows u256"))?;
+        let mut calls = self
+            .calls(&opportunity, minimum_output, executor)
+            .unwrap_or(calls);
+        info!(fee = %total_gas_cost_wei, %limit_fee, %reason, "fee estimation");
+        if limit_fee < total_gas_cost_wei {
+            info!("Fee limit is below the estimated fee");
+            return Ok(None);
+        }
+        // We can make this comparison as both the swapped token and limit fee are nominated in ETH
+        if profit <= TokenAmount::from(limit_fee) {
+            info!("Non-profitable opportunity");
+            return Ok(None);
+        }
+        // The fee estimation only shows that the transaction doesn't fail,
+        // so we check that the balance still rises by the minimum profit plus fees
+        let mut simulation =
+            simulation::simulate(account, calls.to_vec(), nonce, limit_fee, token_address)
+                .instrument(info_span!("simulate"))
+                .await;
+        if let (Some(tolerance_bps), Ok(simulated)) = (slippage_tolerance_bps, &simulation) {
+            match opportunity.tighten_sqrt_ratio_limits(
+                &simulated.swaps,
+                arbitrage_address,
+                tolerance_bps,
+            ) {
+                Ok(()) => {
+                    calls = self
+                        .calls(&opportunity, minimum_output, executor)
+                        .unwrap_or(calls);
+                    // the exact calls to send are simulated
+                    simulation = simulation::simulate(
+                        account,
+                        calls.to_vec(),
+                        nonce,
+                        limit_fee,
+                        token_address,
+                    )
+                    .await;
+                }
+                Err(e) => {
+                    error!(error = %format_args!("{e:#}"), "failed to tighten sqrt ratio limits")
+                }
+            }
+        }
+        let confirmed = match simulation {
+            Ok(simulation) => {
+                info!(
+                    received = %simulation.received,
+                    sent = %simulation.sent,
+                    fee = %simulation.fee,
+                    "simulation"
+                );
+                simulation.is_profitable(min_profit)
+            }
+            Err(e) => {
+                error!(error = %format_args!("{e:#}"), "arbitrage simulation failed");
+                false
+            }
+        };
+        if !confirmed {
+            info!("Opportunity is not profitable in simulation");
+            return Ok(None);
+        }
+        let tx = match account
+            .execute_v1(calls.to_vec())
+            .nonce(nonce)
+            .max_fee(limit_fee)
+            .send()
+            .instrument(info_span!("send"))
+            .await
+        {
+            Ok(tx) => tx,
+            Err(e) => {
+                executor.reset_nonce();
+                return Err(e).wrap_err("Error while sending arbitrage transaction");
+            }
+        };
+        executor.sent(nonce);
+        info!(
+            tx_hash = %format_args!("{:#x}", tx.transaction_hash),
+            "sent transaction: {explorer_url}{:#x}",
+            tx.transaction_hash
+        );
+        notifier
+            .notify(Event::Sent {
+                account: executor.address(),
+                tx_hash: tx.transaction_hash,
+                explorer_url: explorer_url.clone(),
+                amount,
+                profit,
+            })
+            .await;
+        match wait_for_transaction(executor.account.provider(), tx.transaction_hash).await {
+            Ok(receipt) => {
+                let outcome = TradeOutcome::from_receipt(&receipt.receipt, total_gas_cost_wei);
+                info!(
+                    tx_hash = %format_args!("{:#x}", tx.transaction_hash),
+                    fee = %outcome.fee,
+                    reverted = outcome.reverted,
+                    "transaction is accepted"
+                );
+                debug!(
+                    tx_hash = %format_args!("{:#x}", tx.transaction_hash),
+                    block_number = ?receipt.block.block_number(),
+                    finality = ?receipt.receipt.finality_status(),
+                    "receipt"
+                );
+                let event = match receipt.receipt.execution_result() {
+                    ExecutionResult::Reverted { reason } => Event::Reverted {
+                        account: executor.address(),
+                        tx_hash: tx.transaction_hash,
+                        reason: reason.clone(),
+                        fee: outcome.fee,
+                    },
+                    // the trade is done, so a receipt which cannot be read only loses the profit in the event
+                    ExecutionResult::Succeeded => Event::Confirmed {
+                        account: executor.address(),
+                        tx_hash: tx.transaction_hash,
+                        realized_profit: realized_profit(
+                            &receipt.receipt,
+                            token_address,
+                            executor.address(),
+                            outcome.fee,
+                        )
+                        .map_err(|e| error!(error = %format_args!("{e:#}"), "failed to get the realized profit"))
+                        .ok(),
+                        fee: outcome.fee,
+                    },
+                };
+                notifier.notify(event).await;
+                Ok(Some(outcome))
+            }
+            Err(e) => {
+                // a rejected transaction doesn't consume the nonce
+                executor.reset_nonce();
+                error!(
+                    tx_hash = %format_args!("{:#x}", tx.transaction_hash),
+                    error = %format_args!("{e:#}"),
+                    "arbitrage transaction failed"
+                );
+                // the transaction may still be included, so the risk limits count it as reverted at the max fee
+                Ok(Some(TradeOutcome::unconfirmed(
+                    limit_fee,
+                    total_gas_cost_wei,
+                )))
+            }
+        }
+    }
+}
+
+// The balance change of the account by transfers of the token in the receipt minus the fee,
+// signed around the half of the prime
+fn realized_profit(
+    receipt: &TransactionReceipt,
+    token_address: Felt,
+    account_address: Felt,
+    fee: Felt,
+) -> Result<String> {
+    let TransactionReceipt::Invoke(invoke) = receipt else {
+        bail!("arbitrage should be an invoke");
+    };
+    let (received, sent) =
+        simulation::receipt_transfers(&invoke.events, token_address, account_address)?;
+    // the fee transfer of the fee token is already sent
+    let fee = if token_address == simulation::FEE_TOKEN_ADDRESS {
+        Felt::ZERO
+    } else {
+        fee
+    };
+    Ok((u256_to_felt(received) - u256_to_felt(sent) - fee)
+        .to_bigint()
+        .to_string())
+}
+
 fn get_chain_id(ekubo_url: &str, provider_url: &str) -> Result<Felt> {
     if ekubo_url.contains("sepolia") {
         ensure!(
@@ -220,7 +743,7 @@ async fn get_account_balance(
             BlockId::Tag(BlockTag::Latest),
         )
         .await
-        .map_err(|e| eyre!("Error when fetching account balance:\n{e:#?}"))?;
+        .map_err(|e| eyre!("Error when fetching account balance: {e}"))?;
     let low = u128::from_le_bytes(felts[0].to_bytes_le()[0..16].try_into()?);
     let high = u128::from_le_bytes(felts[1].to_bytes_le()[0..16].try_into()?);
     // the unit of data in Cairo is Felt (u252) but ERC20 standard suggests to return u256 from balanceOf
@@ -228,6 +751,14 @@ async fn get_account_balance(
     Ok(U256::from_words(low, high))
 }
 
+fn u256_to_felt(value: U256) -> Felt {
+    Felt::from(value.high()) * Felt::TWO.pow(128u8) + Felt::from(value.low())
+}
+
+fn felt_to_u128(felt: Felt) -> Result<u128> {
+    u128::try_from(felt.to_biguint()).map_err(|_| eyre!("{felt:#x} doesn't fit into u128"))
+}
+
 // Wait for the transaction to be accepted
 async fn wait_for_transaction(
     provider: &JsonRpcClient<HttpTransport>,
@@ -238,11 +769,19 @@ async fn wait_for_transaction(
 
     while retries >= 0 {
         tokio::time::sleep(retry_interval).await; // sleep before the tx status to give some time for a tx get to the provider node
-        let status = provider
-            .get_transaction_status(tx_hash)
-            .await
-            .map_err(|e| eyre!("failed to get tx status: {e:#?}"))?;
         retries -= 1;
+        let status = match provider.get_transaction_status(tx_hash).await {
+            Ok(status) => status,
+            // the node may not know the transaction yet or fail transiently, so it is asked again until the timeout
+            Err(e) => {
+                debug!(
+                    tx_hash = %format_args!("{tx_hash:#x}"),
+                    error = %format_args!("{e:#}"),
+                    "failed to get tx status"
+                );
+                continue;
+            }
+        };
         match status {
             TransactionStatus::Received => continue,
             TransactionStatus::Rejected => bail!("transaction is rejected"),
@@ -270,33 +809,82 @@ enum Mode {
 
 /// Ekubo arbitrage bot
 #[derive(Parser)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
     /// Bot mode
-    #[arg(value_enum)]
-    mode: Mode,
+    #[arg(value_enum, required = true)]
+    mode: Option<Mode>,
     /// Path to .env file
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     path: Option<PathBuf>,
 }
 
-#[allow(unreachable_code)]
+#[derive(Subcommand)]
+enum Command {
+    /// Replay recorded quote snapshots and report trades and PnL for strategy settings
+    Backtest(backtest::BacktestArgs),
+    /// Rank combinations of strategy settings by PnL on recorded quote snapshots
+    Sweep(backtest::SweepArgs),
+}
+
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> Result<()> {
     let args = Cli::parse();
     if let Some(path) = args.path {
         dotenvy::from_path(path)?;
     } else {
-        dotenvy::dotenv()?;
+        let loaded = dotenvy::dotenv();
+        // commands don't require .env
+        if args.command.is_none() {
+            loaded?;
+        }
     }
     color_eyre::install()?;
-    tracing_subscriber::registry()
-        .with(fmt::layer())
-        .with(EnvFilter::from_default_env())
-        .init();
-    let token_address_hex = env::var("TOKEN_TO_ARBITRAGE")?;
-    let token_address = Felt::from_hex(&token_address_hex)?;
-    let arbitrage_address_hex = match args.mode {
+    telemetry::init()?;
+    match (args.command, args.mode) {
+        (Some(Command::Backtest(backtest)), _) => backtest::run(&backtest),
+        (Some(Command::Sweep(sweep)), _) => backtest::sweep(&sweep),
+        (None, Some(mode)) => run_bot(mode).await,
+        (None, None) => bail!("either a bot mode or a command is required, see --help"),
+    }
+}
+
+// optional credentials for an authenticated Ekubo API endpoint or a proxy
+fn ekubo_client(url: String) -> Result<Client> {
+    let mut builder = Client::builder(url).user_agent("atomic-bot".to_string());
+    if let Ok(key) = env::var("EKUBO_API_KEY") {
+        builder = builder.api_key(key);
+    }
+    if let Ok(token) = env::var("EKUBO_BEARER_TOKEN") {
+        builder = builder.bearer_token(token);
+    }
+    // `Name: value` pairs separated by `;`
+    if let Ok(headers) = env::var("EKUBO_HEADERS") {
+        for header in headers
+            .split(';')
+            .filter(|header| !header.trim().is_empty())
+        {
+            let (name, value) = header.split_once(':').ok_or_else(|| {
+                eyre!("EKUBO_HEADERS should be `Name: value` pairs separated by `;`")
+            })?;
+            builder = builder.header(name.trim().to_string(), value.trim().to_string());
+        }
+    }
+    builder.build()
+}
+
+#[allow(unreachable_code)]
+async fn run_bot(mode: Mode) -> Result<()> {
+    let token_address = Felt::from_hex(&env::var("TOKEN_TO_ARBITRAGE")?)?;
+    let arbitrage_address_hex = match mode {
         Mode::Simple => env::var("ROUTER_ADDRESS")?,
         Mode::EkuboFlash => env::var("ARBITRAGE_CONTRACT")?,
     };
@@ -306,29 +894,48 @@ async fn main() -> Result<()> {
     let explorer_url = env::var("EXPLORER_TX_PREFIX")?;
     info!(
         "starting bot with Ekubo API {url} and RPC {provider_url}, strategy: {:?}",
-        args.mode
+        mode
     );
     let chain_id = get_chain_id(&url, &provider_url)?;
 
-    let client = Client::new(url, "atomic-bot".to_string());
-    let rpc_transport = HttpTransport::new(Url::parse(&provider_url)?);
+    let client = ekubo_client(url)?;
+    // the token list is informational, the bot works with unlisted tokens too
+    let tokens = client.token_registry().await.unwrap_or_else(|e| {
+        warn!(error = %format_args!("{e:#}"), "failed to fetch the token list");
+        TokenRegistry::default()
+    });
+    let token = tokens.get(token_address).cloned();
+    match &token {
+        Some(token) => info!(
+            symbol = %token.symbol,
+            decimals = token.decimals,
+            "arbitraging {}",
+            ekubo::canonical_hex(token_address)
+        ),
+        None => warn!(
+            "token {} is not listed by Ekubo",
+            ekubo::canonical_hex(token_address)
+        ),
+    }
+    let provider_url = Url::parse(&provider_url)?;
+    let rpc_transport = HttpTransport::new(provider_url.clone());
+    // the provider for reading, every executor has its own one
     let provider = JsonRpcClient::new(rpc_transport);
     ensure!(chain_id == provider.chain_id().await?);
 
-    let signer = LocalWallet::from(SigningKey::from_secret_scalar(Felt::from_hex(&env::var(
-        "ACCOUNT_PRIVATE_KEY",
-    )?)?));
-    let account_address = Felt::from_hex(&env::var("ACCOUNT_ADDRESS")?)?;
-
-    let mut account = SingleOwnerAccount::new(
-        provider,
-        signer,
-        account_address,
-        chain_id,
-        ExecutionEncoding::New, // https://docs.rs/starknet/0.11.0/starknet/accounts/enum.ExecutionEncoding.html#variant.New,
+    let mut executors = vec![];
+    for suffix in executor::executor_suffixes()? {
+        executors.push(Executor::from_env(&suffix, &provider_url, chain_id).await?);
+    }
+    ensure!(
+        !executors.is_empty(),
+        "at least one executor account is required"
+    );
+    // optional: top up and sweep executors from a cold account
+    let mut treasury = Treasury::from_env(&provider_url, chain_id).await?;
+    let treasury_timeout = Duration::from_millis(
+        env::var("TREASURY_TIMEOUT_MS").map_or(Ok(60_000), |timeout| timeout.parse())?,
     );
-    // otherwise we will get a DuplicateTx error as nonce by default set to the latest block
-    account.set_block_id(BlockId::Tag(BlockTag::Pending));
 
     let min_power: u8 = 32.max(env::var("MIN_POWER_OF_2")?.parse()?);
     let max_power: u8 = (min_power + 1).max(65.min(env::var("MAX_POWER_OF_2")?.parse()?));
@@ -339,84 +946,307 @@ async fn main() -> Result<()> {
 
     let max_splits: u8 = env::var("MAX_SPLITS")?.parse()?;
     let max_hops: u8 = env::var("MAX_HOPS")?.parse()?;
-    let min_profit = Felt::from_dec_str(&env::var("MIN_PROFIT")?)?;
+    let min_profit: TokenAmount = env::var("MIN_PROFIT")?.parse()?;
     let num_top_quotes: usize = env::var("NUM_TOP_QUOTES_TO_ESTIMATE")?.parse()?;
-    let check_interval: u64 = env::var("CHECK_INTERVAL_MS")?.parse()?;
-    // We only check arbitrage opporunities in pools without extensions or with an official extensions only
-    // to prevent any front-running or other activities
-    // an official extension https://docs.ekubo.org/integration-guides/reference/contract-addresses
-    let twamm_extension =
-        Felt::from_hex("0x043e4f09c32d13d43a880e85f69f7de93ceda62d6cf2581a582c6db635548fdc")?;
-    let official_extensions = HashSet::from([twamm_extension]);
-
-    loop {
-        let account_balance =
-            get_account_balance(token_address, account_address, account.provider()).await?;
-        info!("Account balance: {account_balance} WEI");
-
-        let mut opportunities: Vec<ArbitrageOpportunity> = join_all(
-            amounts_to_quote
+    // optional slippage tolerance (in basis points of the price) for per hop sqrt ratio limits
+    let slippage_tolerance_bps: Option<u16> = match env::var("SLIPPAGE_TOLERANCE_BPS") {
+        Ok(tolerance) => {
+            let tolerance = tolerance.parse()?;
+            ensure!(
+                tolerance <= 10_000,
+                "SLIPPAGE_TOLERANCE_BPS should be at most 10000"
+            );
+            Some(tolerance)
+        }
+        Err(_) => None,
+    };
+    let check_interval = Duration::from_millis(env::var("CHECK_INTERVAL_MS")?.parse()?);
+    let pools = PoolFilter::from_env()?;
+    // optional: quotes of every scan round are saved for backtesting
+    let record_dir = env::var("RECORD_QUOTES_DIR").ok().map(PathBuf::from);
+    // If Ekubo Core address is provided, the next scan is triggered only after some pool is moved by a swap
+    // https://docs.ekubo.org/integration-guides/reference/contract-addresses#immutable-contracts
+    let mut indexer = match env::var("EKUBO_CORE_ADDRESS") {
+        Ok(core_address) => Some(Indexer::new(
+            Felt::from_hex(&core_address)?,
+            indexer::latest_block(&provider).await?,
+        )?),
+        Err(_) => None,
+    };
+    // By default a scan is run every CHECK_INTERVAL_MS,
+    // `block` and `pending` triggers start a scan as soon as a new (pending) block is seen
+    let block_poll_interval = Duration::from_millis(
+        env::var("BLOCK_POLL_INTERVAL_MS").map_or(Ok(1000), |interval| interval.parse())?,
+    );
+    let mut trigger = Trigger::new(
+        &env::var("SCAN_TRIGGER").unwrap_or_else(|_| "interval".to_string()),
+        check_interval,
+        block_poll_interval,
+        env::var("JSON_RPC_WS_URL").ok(),
+        &provider,
+    )
+    .await?;
+
+    // limits on losses and fees pause sending, scanning continues
+    let mut risk = Risk::from_env()?;
+    // how much over the estimated fee we are ready to pay
+    let mut fee_policy = FeePolicy::from_env()?;
+
+    let settings = Settings {
+        mode,
+        arbitrage_address,
+        token_address,
+        token,
+        tokens,
+        min_profit,
+        slippage_tolerance_bps,
+        explorer_url,
+        notifier: Notifier::from_env()?,
+    };
+    settings
+        .notifier
+        .notify(Event::Started {
+            mode: format!("{:?}", mode),
+            accounts: executors.iter().map(Executor::address).collect(),
+        })
+        .await;
+    // optional: notify once an executor balance drops below the threshold
+    let low_balance_alert = match env::var("LOW_BALANCE_ALERT") {
+        Ok(threshold) => Some(U256::from(Felt::from_dec_str(&threshold)?)),
+        Err(_) => None,
+    };
+    let mut low_balances: HashSet<Felt> = HashSet::new();
+
+    for round in 0u64.. {
+        async {
+            for executor in executors.iter_mut() {
+                let balance = executor.refresh_balance(token_address).await?;
+                info!(
+                    account = %format_args!("{:#x}", executor.address()),
+                    %balance,
+                    "account balance, WEI"
+                );
+            }
+            if let Some(threshold) = low_balance_alert {
+                for executor in &executors {
+                    if executor.balance >= threshold {
+                        low_balances.remove(&executor.address());
+                    } else if low_balances.insert(executor.address()) {
+                        settings
+                            .notifier
+                            .notify(Event::LowBalance {
+                                account: executor.address(),
+                                balance: executor.balance.to_string(),
+                                threshold: threshold.to_string(),
+                            })
+                            .await;
+                    }
+                }
+            }
+            let max_balance = executors
                 .iter()
-                .filter(|&&amount| {
-                    args.mode != Mode::Simple || U256::from(amount) <= account_balance
-                })
-                .map(|&amount| {
-                    check_arbitrage(
-                        &client,
-                        amount,
-                        min_profit,
-                        &token_address_hex,
-                        max_splits,
-                        max_hops,
-                        &official_extensions,
-                    )
-                }),
-        )
-        .await
-        .into_iter()
-        .flatten()
-        .collect();
-        opportunities.sort_unstable_by_key(|opportunity| Reverse(opportunity.profit));
-        let top: Option<(Felt, Felt, Vec<Call>)> = opportunities
+                .map(|executor| executor.balance)
+                .max()
+                .unwrap_or(U256::from(0u8));
+
+            let quotes: Vec<(Felt, Quotes)> = join_all(
+                amounts_to_quote
+                    .iter()
+                    .filter(|&&amount| mode != Mode::Simple || U256::from(amount) <= max_balance)
+                    .filter(|&&amount| risk.allows_notional(amount))
+                    .map(|&amount| {
+                        fetch_quotes(
+                            &client,
+                            &settings.tokens,
+                            amount,
+                            token_address,
+                            max_splits,
+                            max_hops,
+                        )
+                    }),
+            )
+            .await
             .into_iter()
-            .take(num_top_quotes)
-            .find_map(|opportunity| {
-                opportunity.into_strategy(arbitrage_address, token_address, args.mode)
-            });
-        if let Some((profit, amount, calls)) = top {
-            info!("top arbitrage profit: {profit}, amount {amount}");
-            info!("Executing top arbitrage:\n{calls:#?}");
-            let cost = account.execute_v1(calls.to_vec()).estimate_fee().await?;
-            // gas fees in WEI as we use tx v1,
-            // see https://docs.rs/starknet/0.11.0/starknet/core/types/struct.FeeEstimate.html
-            let total_gas_cost_wei = cost.overall_fee;
-            info!("cost etimation:\n{total_gas_cost_wei:#?}");
-            // Get a tx receipt, actual fee, link to the explorer with tx
-            let limit_fee = total_gas_cost_wei * Felt::TWO;
-            info!("cost etimation:\n{total_gas_cost_wei}");
-            info!("profit etimation:\n{profit}, limit fee:\n{limit_fee}");
-            // We can make this comparison as both the swapped token and limit fee are nominated in ETH
-            if profit > limit_fee {
-                let tx = account
-                    .execute_v1(calls.to_vec())
-                    .max_fee(limit_fee)
-                    .send()
-                    .await
-                    .map_err(|e| eyre!("Error while sending arbitrage transaction:\n{e:#?}"))?;
+            .flatten()
+            .collect();
+            if let Some(dir) = &record_dir {
+                let snapshot = Snapshot {
+                    block: provider.block_number().await.ok(),
+                    token_address,
+                    quotes: quotes.clone(),
+                };
+                if let Err(e) = snapshot.save(dir) {
+                    error!(error = %format_args!("{e:#}"), "failed to record quotes");
+                }
+            }
+            let mut opportunities: Vec<ArbitrageOpportunity> = quotes
+                .into_iter()
+                .filter_map(|(amount, quotes)| check_arbitrage(amount, quotes, min_profit, &pools))
+                .collect();
+            opportunities.sort_unstable_by_key(|opportunity| Reverse(opportunity.profit));
+            let mut candidates: Vec<ArbitrageOpportunity> = opportunities
+                .into_iter()
+                .take(num_top_quotes)
+                .filter(|opportunity| {
+                    opportunity
+                        .minimum_output(min_profit, Felt::ZERO)
+                        .zip(executors.first())
+                        .and_then(|(minimum_output, executor)| {
+                            settings.calls(opportunity, minimum_output, executor)
+                        })
+                        .is_some()
+                })
+                .collect();
+            let paused = !risk.check();
+            if paused {
                 info!(
-                    "sent transaction:\n{explorer_url}{:#x}",
-                    tx.transaction_hash
+                    "Sending is paused by the circuit breaker: {}",
+                    risk.tripped().unwrap_or_default()
                 );
-                match wait_for_transaction(account.provider(), tx.transaction_hash).await {
-                    Ok(receipt) => info!("Transaction receipt: {receipt:#?}"),
-                    Err(e) => error!("Arbitrage transaction failed: {e:#?}"),
+                candidates.clear();
+            }
+            let candidates = settings.rank_by_net_profit(candidates, &executors).await;
+            let results = join_all(schedule(candidates, &mut executors, mode).into_iter().map(
+                |(executor, opportunity)| settings.execute(executor, opportunity, &fee_policy),
+            ))
+            .await;
+            // an error of one executor doesn't lose outcomes of the others
+            for result in results {
+                match result {
+                    Ok(Some(outcome)) => {
+                        if outcome.confirmed {
+                            fee_policy.record(outcome.estimated_fee, outcome.fee);
+                        }
+                        risk.record(outcome);
+                    }
+                    Ok(None) => {}
+                    Err(e) => error!(error = %format_args!("{e:#}"), "arbitrage execution failed"),
                 }
-            } else {
-                info!("Non-profitable opportunity");
             }
+            // trip the breaker as soon as the limit is reached
+            if !risk.check() && !paused {
+                settings
+                    .notifier
+                    .notify(Event::BreakerTripped {
+                        reason: risk.tripped().unwrap_or_default().to_string(),
+                    })
+                    .await;
+            }
+            // the first free executor gets the best opportunity, so executors take turns
+            executors.rotate_left(1);
+            Ok::<(), Report>(())
+        }
+        .instrument(info_span!("scan", round))
+        .await?;
+        // transfers are confirmed between rounds, so they don't hold up sending of opportunities
+        if let Some(treasury) = treasury.as_mut() {
+            match timeout(
+                treasury_timeout,
+                treasury.rebalance(&mut executors, token_address),
+            )
+            .instrument(info_span!("rebalance", round))
+            .await
+            {
+                Ok(Ok(())) => {}
+                Ok(Err(e)) => error!(error = %format_args!("{e:#}"), "treasury rebalancing failed"),
+                Err(_) => warn!(
+                    timeout_ms = treasury_timeout.as_millis(),
+                    "treasury rebalancing timed out"
+                ),
+            }
+        }
+        loop {
+            trigger.wait(&provider).await;
+            if let Some(indexer) = indexer.as_mut() {
+                // the indexer catches up on the next tick
+                let moved = match indexer.sync(&provider).await {
+                    Ok(moved) => moved,
+                    Err(e) => {
+                        error!(error = %format_args!("{e:#}"), "indexer sync failed");
+                        continue;
+                    }
+                };
+                for pool_key in &moved {
+                    if let Some(state) = indexer.pool(pool_key) {
+                        debug!(
+                            pool_id = %format_args!("{:#x}", pool_key.pool_id()),
+                            "pool {:#x}/{:#x} fee {}% tick spacing {} ({:.4}%) moved at block {}: sqrt_ratio {}, tick {}, liquidity {}",
+                            pool_key.token0,
+                            pool_key.token1,
+                            pool_key.fee_percent(),
+                            pool_key.tick_spacing,
+                            pool_key.price_granularity() * 100.0,
+                            state.block_number,
+                            state.sqrt_ratio,
+                            state.tick,
+                            state.liquidity
+                        );
+                    }
+                }
+                if moved.is_empty() {
+                    continue;
+                }
+                info!("{} pools moved by swaps, scanning", moved.len());
+            }
+            break;
         }
-        sleep(Duration::from_millis(check_interval)).await;
     }
 
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ekubo::validation::{MAX_SQRT_RATIO, MIN_SQRT_RATIO};
+
+    // 2^128 is the price of 1
+    fn one() -> U256 {
+        U256::from_words(0, 1)
+    }
+
+    #[test]
+    fn tightens_the_limit_towards_the_expected_price() {
+        // 100 bps of the price are 50 bps of the sqrt ratio
+        let down = tightened_sqrt_ratio_limit(MIN_SQRT_RATIO, one(), 100);
+        assert_eq!(
+            U256::from(down),
+            one() * U256::from(19_900u32) / U256::from(20_000u32)
+        );
+        let up = tightened_sqrt_ratio_limit(MAX_SQRT_RATIO, one(), 100);
+        assert_eq!(
+            U256::from(up),
+            one() * U256::from(20_100u32) / U256::from(20_000u32)
+        );
+        // no tolerance is the expected price itself
+        assert_eq!(
+            U256::from(tightened_sqrt_ratio_limit(MAX_SQRT_RATIO, one(), 0)),
+            one()
+        );
+    }
+
+    #[test]
+    fn never_looser_than_the_api_limit() {
+        let api_down = u256_to_felt(one() * U256::from(999u32) / U256::from(1_000u32));
+        assert_eq!(
+            tightened_sqrt_ratio_limit(api_down, one(), 10_000),
+            api_down
+        );
+        let api_up = u256_to_felt(one() * U256::from(1_001u32) / U256::from(1_000u32));
+        assert_eq!(tightened_sqrt_ratio_limit(api_up, one(), 10_000), api_up);
+    }
+
+    #[test]
+    fn stays_within_ekubo_core_bounds() {
+        let at_max = tightened_sqrt_ratio_limit(MAX_SQRT_RATIO, U256::from(MAX_SQRT_RATIO), 10_000);
+        assert_eq!(at_max, MAX_SQRT_RATIO);
+        let at_min = tightened_sqrt_ratio_limit(MIN_SQRT_RATIO, U256::from(MIN_SQRT_RATIO), 10_000);
+        assert_eq!(at_min, MIN_SQRT_RATIO);
+        let near_min = tightened_sqrt_ratio_limit(
+            MIN_SQRT_RATIO,
+            U256::from(MIN_SQRT_RATIO + Felt::ONE),
+            10_000,
+        );
+        assert!(near_min >= MIN_SQRT_RATIO);
+    }
+}
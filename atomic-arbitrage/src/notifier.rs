@@ -0,0 +1,294 @@
+use color_eyre::eyre::Result;
+use ekubo::TokenAmount;
+use serde_json::{json, Value};
+use starknet::{core::types::Felt, providers::Url};
+use std::env;
+use tokio::time::Duration;
+use tracing::warn;
+
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What the on-call people should know about
+pub enum Event {
+    Started {
+        mode: String,
+        accounts: Vec<Felt>,
+    },
+    Sent {
+        account: Felt,
+        tx_hash: Felt,
+        explorer_url: String,
+        amount: TokenAmount,
+        profit: TokenAmount,
+    },
+    Confirmed {
+        account: Felt,
+        tx_hash: Felt,
+        // signed, the fee is subtracted, `None` if the receipt cannot be read
+        realized_profit: Option<String>,
+        fee: Felt,
+    },
+    Reverted {
+        account: Felt,
+        tx_hash: Felt,
+        reason: String,
+        fee: Felt,
+    },
+    LowBalance {
+        account: Felt,
+        balance: String,
+        threshold: String,
+    },
+    BreakerTripped {
+        reason: String,
+    },
+}
+
+impl Event {
+    fn payload(&self) -> Value {
+        match self {
+            Self::Started { mode, accounts } => json!({
+                "event": "started",
+                "mode": mode,
+                "accounts": accounts.iter().map(|account| format!("{account:#x}")).collect::<Vec<_>>(),
+            }),
+            Self::Sent {
+                account,
+                tx_hash,
+                explorer_url,
+                amount,
+                profit,
+            } => json!({
+                "event": "sent",
+                "account": format!("{account:#x}"),
+                "tx_hash": format!("{tx_hash:#x}"),
+                "explorer_url": format!("{explorer_url}{tx_hash:#x}"),
+                "amount": amount.to_string(),
+                "profit": profit.to_string(),
+            }),
+            Self::Confirmed {
+                account,
+                tx_hash,
+                realized_profit,
+                fee,
+            } => json!({
+                "event": "confirmed",
+                "account": format!("{account:#x}"),
+                "tx_hash": format!("{tx_hash:#x}"),
+                "realized_profit": realized_profit,
+                "fee": fee.to_string(),
+            }),
+            Self::Reverted {
+                account,
+                tx_hash,
+                reason,
+                fee,
+            } => json!({
+                "event": "reverted",
+                "account": format!("{account:#x}"),
+                "tx_hash": format!("{tx_hash:#x}"),
+                "reason": reason,
+                "fee": fee.to_string(),
+            }),
+            Self::LowBalance {
+                account,
+                balance,
+                threshold,
+            } => json!({
+                "event": "low_balance",
+                "account": format!("{account:#x}"),
+                "balance": balance,
+                "threshold": threshold,
+            }),
+            Self::BreakerTripped { reason } => json!({
+                "event": "breaker_tripped",
+                "reason": reason,
+            }),
+        }
+    }
+}
+
+/// Posts events as JSON to `WEBHOOK_URL` (amounts are decimal WEI strings).
+/// Without the url events are dropped, a failed delivery is only logged.
+pub struct Notifier {
+    client: reqwest::Client,
+    url: Option<Url>,
+}
+
+impl Notifier {
+    pub fn from_env() -> Result<Self> {
+        let url = match env::var("WEBHOOK_URL") {
+            Ok(url) => Some(Url::parse(&url)?),
+            Err(_) => None,
+        };
+        let client = reqwest::Client::builder()
+            .timeout(WEBHOOK_TIMEOUT)
+            .build()?;
+        Ok(Self { client, url })
+    }
+
+    pub async fn notify(&self, event: Event) {
+        let Some(url) = &self.url else {
+            return;
+        };
+        let payload = event.payload();
+        if let Err(e) = self
+            .client
+            .post(url.clone())
+            .json(&payload)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+        {
+            warn!("failed to deliver {payload} to the webhook: {e}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    // A webhook stand-in which answers `200 OK` to every request and passes its JSON body on
+    fn webhook(requests: usize) -> (Url, mpsc::Receiver<Value>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = Url::parse(&format!("http://{}/alerts", listener.local_addr().unwrap())).unwrap();
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for stream in listener.incoming().take(requests) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut body = vec![0; content_length];
+                reader.read_exact(&mut body).unwrap();
+                stream
+                    .write_all(b"HTTP/1.1 200 OK\r\nContent-Length: 0\r\nConnection: close\r\n\r\n")
+                    .unwrap();
+                sender.send(serde_json::from_slice(&body).unwrap()).unwrap();
+            }
+        });
+        (url, receiver)
+    }
+
+    #[tokio::test]
+    async fn posts_every_event_as_json() {
+        let account = Felt::from_hex("0xabc").unwrap();
+        let tx_hash = Felt::from_hex("0x123").unwrap();
+        let events = vec![
+            (
+                Event::Started {
+                    mode: "Simple".to_string(),
+                    accounts: vec![account],
+                },
+                json!({"event": "started", "mode": "Simple", "accounts": ["0xabc"]}),
+            ),
+            (
+                Event::Sent {
+                    account,
+                    tx_hash,
+                    explorer_url: "https://voyager.online/tx/".to_string(),
+                    amount: TokenAmount::from(1_000_u128),
+                    profit: TokenAmount::from(15_u128),
+                },
+                json!({
+                    "event": "sent",
+                    "account": "0xabc",
+                    "tx_hash": "0x123",
+                    "explorer_url": "https://voyager.online/tx/0x123",
+                    "amount": "1000",
+                    "profit": "15",
+                }),
+            ),
+            (
+                Event::Confirmed {
+                    account,
+                    tx_hash,
+                    realized_profit: Some("-5".to_string()),
+                    fee: Felt::from(20_u8),
+                },
+                json!({
+                    "event": "confirmed",
+                    "account": "0xabc",
+                    "tx_hash": "0x123",
+                    "realized_profit": "-5",
+                    "fee": "20",
+                }),
+            ),
+            (
+                Event::Confirmed {
+                    account,
+                    tx_hash,
+                    realized_profit: None,
+                    fee: Felt::from(20_u8),
+                },
+                json!({
+                    "event": "confirmed",
+                    "account": "0xabc",
+                    "tx_hash": "0x123",
+                    "realized_profit": null,
+                    "fee": "20",
+                }),
+            ),
+            (
+                Event::Reverted {
+                    account,
+                    tx_hash,
+                    reason: "CLEAR_AT_LEAST_MINIMUM".to_string(),
+                    fee: Felt::from(20_u8),
+                },
+                json!({
+                    "event": "reverted",
+                    "account": "0xabc",
+                    "tx_hash": "0x123",
+                    "reason": "CLEAR_AT_LEAST_MINIMUM",
+                    "fee": "20",
+                }),
+            ),
+            (
+                Event::LowBalance {
+                    account,
+                    balance: "5".to_string(),
+                    threshold: "10".to_string(),
+                },
+                json!({
+                    "event": "low_balance",
+                    "account": "0xabc",
+                    "balance": "5",
+                    "threshold": "10",
+                }),
+            ),
+            (
+                Event::BreakerTripped {
+                    reason: "3 consecutive reverts".to_string(),
+                },
+                json!({"event": "breaker_tripped", "reason": "3 consecutive reverts"}),
+            ),
+        ];
+        let (url, received) = webhook(events.len());
+        let notifier = Notifier {
+            client: reqwest::Client::new(),
+            url: Some(url),
+        };
+        for (event, expected) in events {
+            notifier.notify(event).await;
+            assert_eq!(received.recv().unwrap(), expected);
+        }
+    }
+}
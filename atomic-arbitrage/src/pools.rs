@@ -0,0 +1,48 @@
+use color_eyre::eyre::Result;
+use ekubo::models::PoolKey;
+use starknet::core::types::Felt;
+use std::collections::HashSet;
+use std::env;
+
+/// Pools which routes of opportunities may go through:
+/// * pools without extensions or with official extensions only, to prevent any front-running or other activities
+///   (see https://docs.ekubo.org/integration-guides/reference/contract-addresses)
+/// * `DENIED_POOLS` - Ekubo Core pool ids separated by `,` are skipped
+/// * `MAX_POOL_FEE_BPS` - pools with a higher fee are skipped (e.g. 100 for 1%)
+pub struct PoolFilter {
+    official_extensions: HashSet<Felt>,
+    denied: HashSet<Felt>,
+    max_fee_bps: Option<f64>,
+}
+
+impl PoolFilter {
+    pub fn from_env() -> Result<Self> {
+        let twamm_extension =
+            Felt::from_hex("0x043e4f09c32d13d43a880e85f69f7de93ceda62d6cf2581a582c6db635548fdc")?;
+        let mut denied = HashSet::new();
+        if let Ok(pools) = env::var("DENIED_POOLS") {
+            for pool_id in pools
+                .split(',')
+                .filter(|pool_id| !pool_id.trim().is_empty())
+            {
+                denied.insert(Felt::from_hex(pool_id.trim())?);
+            }
+        }
+        Ok(Self {
+            official_extensions: HashSet::from([twamm_extension]),
+            denied,
+            max_fee_bps: match env::var("MAX_POOL_FEE_BPS") {
+                Ok(max_fee_bps) => Some(max_fee_bps.parse()?),
+                Err(_) => None,
+            },
+        })
+    }
+
+    pub fn allows(&self, pool_key: &PoolKey) -> bool {
+        (pool_key.extension == Felt::ZERO || self.official_extensions.contains(&pool_key.extension))
+            && !self.denied.contains(&pool_key.pool_id())
+            && self
+                .max_fee_bps
+                .is_none_or(|max_fee_bps| pool_key.fee_bps() <= max_fee_bps)
+    }
+}
@@ -0,0 +1,286 @@
+use color_eyre::eyre::Result;
+use starknet::core::types::{ExecutionResult, Felt, TransactionReceipt};
+use std::collections::VecDeque;
+use std::env;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+const HOUR: Duration = Duration::from_secs(60 * 60);
+const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+/// The fee paid by a sent arbitrage transaction
+pub struct TradeOutcome {
+    pub fee: Felt,
+    pub estimated_fee: Felt,
+    pub reverted: bool,
+    /// `false` if the receipt is unknown and the outcome is assumed
+    pub confirmed: bool,
+}
+
+impl TradeOutcome {
+    pub fn from_receipt(receipt: &TransactionReceipt, estimated_fee: Felt) -> Self {
+        let fee = match receipt {
+            TransactionReceipt::Invoke(receipt) => receipt.actual_fee.amount,
+            _ => Felt::ZERO,
+        };
+        Self {
+            fee,
+            estimated_fee,
+            reverted: matches!(receipt.execution_result(), ExecutionResult::Reverted { .. }),
+            confirmed: true,
+        }
+    }
+
+    /// The worst case of a sent transaction without a receipt: reverted at the max fee
+    pub fn unconfirmed(max_fee: Felt, estimated_fee: Felt) -> Self {
+        Self {
+            fee: max_fee,
+            estimated_fee,
+            reverted: true,
+            confirmed: false,
+        }
+    }
+}
+
+/// Limits which pause sending of transactions (scanning continues):
+/// * `MAX_LOSS_PER_HOUR`, `MAX_LOSS_PER_DAY` - fees paid by reverted transactions, WEI
+/// * `MAX_GAS_PER_PERIOD` - fees paid by all transactions during `GAS_PERIOD_MS` (an hour by default), WEI
+/// * `MAX_CONSECUTIVE_REVERTS` - the pause lasts until restart
+/// * `MAX_NOTIONAL_PER_TRADE` - larger input amounts are skipped, WEI
+///
+/// Window limits are lifted as soon as old fees leave the window.
+pub struct Risk {
+    max_loss_per_hour: Option<Felt>,
+    max_loss_per_day: Option<Felt>,
+    max_gas_per_period: Option<Felt>,
+    gas_period: Duration,
+    max_consecutive_reverts: Option<u32>,
+    max_notional_per_trade: Option<Felt>,
+    // fees paid for the last day (or the gas period if longer)
+    fees: VecDeque<(Instant, TradeOutcome)>,
+    consecutive_reverts: u32,
+    tripped: Option<String>,
+}
+
+impl Risk {
+    pub fn from_env() -> Result<Self> {
+        Ok(Self {
+            max_loss_per_hour: amount_from_env("MAX_LOSS_PER_HOUR")?,
+            max_loss_per_day: amount_from_env("MAX_LOSS_PER_DAY")?,
+            max_gas_per_period: amount_from_env("MAX_GAS_PER_PERIOD")?,
+            gas_period: Duration::from_millis(
+                env::var("GAS_PERIOD_MS")
+                    .map_or(Ok(HOUR.as_millis() as u64), |period| period.parse())?,
+            ),
+            max_consecutive_reverts: match env::var("MAX_CONSECUTIVE_REVERTS") {
+                Ok(reverts) => Some(reverts.parse()?),
+                Err(_) => None,
+            },
+            max_notional_per_trade: amount_from_env("MAX_NOTIONAL_PER_TRADE")?,
+            fees: VecDeque::new(),
+            consecutive_reverts: 0,
+            tripped: None,
+        })
+    }
+
+    pub fn allows_notional(&self, amount: Felt) -> bool {
+        match self.max_notional_per_trade {
+            Some(max_notional) => amount <= max_notional,
+            None => true,
+        }
+    }
+
+    pub fn record(&mut self, outcome: TradeOutcome) {
+        if outcome.reverted {
+            self.consecutive_reverts += 1;
+        } else {
+            self.consecutive_reverts = 0;
+        }
+        self.fees.push_back((Instant::now(), outcome));
+    }
+
+    /// Whether transactions can be sent, the breaker state change is logged
+    pub fn check(&mut self) -> bool {
+        let now = Instant::now();
+        let retention = DAY.max(self.gas_period);
+        while let Some((at, _)) = self.fees.front() {
+            if now.duration_since(*at) <= retention {
+                break;
+            }
+            self.fees.pop_front();
+        }
+        let reason = self.violation(now);
+        match (&self.tripped, &reason) {
+            (None, Some(reason)) => {
+                error!("Circuit breaker is tripped, sending is paused: {reason}")
+            }
+            (Some(_), None) => info!("Circuit breaker is reset, sending is resumed"),
+            _ => {}
+        }
+        self.tripped = reason;
+        self.tripped.is_none()
+    }
+
+    pub fn tripped(&self) -> Option<&str> {
+        self.tripped.as_deref()
+    }
+
+    fn violation(&self, now: Instant) -> Option<String> {
+        if let Some(max_reverts) = self.max_consecutive_reverts {
+            if self.consecutive_reverts >= max_reverts {
+                return Some(format!(
+                    "{} consecutive reverts, restart the bot to resume",
+                    self.consecutive_reverts
+                ));
+            }
+        }
+        let checks = [
+            ("loss per hour", self.max_loss_per_hour, HOUR, true),
+            ("loss per day", self.max_loss_per_day, DAY, true),
+            (
+                "gas per period",
+                self.max_gas_per_period,
+                self.gas_period,
+                false,
+            ),
+        ];
+        for (name, limit, window, only_reverted) in checks {
+            let Some(limit) = limit else {
+                continue;
+            };
+            let spent = self
+                .fees
+                .iter()
+                .filter(|(at, outcome)| {
+                    now.duration_since(*at) <= window && (outcome.reverted || !only_reverted)
+                })
+                .fold(Felt::ZERO, |spent, (_, outcome)| spent + outcome.fee);
+            if spent >= limit {
+                return Some(format!("{name} {spent} WEI reached the limit {limit} WEI"));
+            }
+        }
+        None
+    }
+}
+
+// amounts are decimal WEI like MIN_PROFIT
+fn amount_from_env(name: &str) -> Result<Option<Felt>> {
+    match env::var(name) {
+        Ok(amount) => Ok(Some(Felt::from_dec_str(&amount)?)),
+        Err(_) => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn no_limits() -> Risk {
+        Risk {
+            max_loss_per_hour: None,
+            max_loss_per_day: None,
+            max_gas_per_period: None,
+            gas_period: HOUR,
+            max_consecutive_reverts: None,
+            max_notional_per_trade: None,
+            fees: VecDeque::new(),
+            consecutive_reverts: 0,
+            tripped: None,
+        }
+    }
+
+    fn outcome(fee: u64, reverted: bool) -> TradeOutcome {
+        TradeOutcome {
+            fee: Felt::from(fee),
+            estimated_fee: Felt::from(fee),
+            reverted,
+            confirmed: true,
+        }
+    }
+
+    #[test]
+    fn notional_limit() {
+        let mut risk = no_limits();
+        assert!(risk.allows_notional(Felt::from(u128::MAX)));
+        risk.max_notional_per_trade = Some(Felt::from(1_000u32));
+        assert!(risk.allows_notional(Felt::from(1_000u32)));
+        assert!(!risk.allows_notional(Felt::from(1_001u32)));
+    }
+
+    #[test]
+    fn loss_counts_reverted_fees_only() {
+        let mut risk = Risk {
+            max_loss_per_hour: Some(Felt::from(100u8)),
+            ..no_limits()
+        };
+        risk.record(outcome(90, false));
+        risk.record(outcome(60, true));
+        assert!(risk.check());
+        risk.record(outcome(40, true));
+        assert!(!risk.check());
+        assert!(risk.tripped().unwrap().starts_with("loss per hour 100 WEI"));
+    }
+
+    #[test]
+    fn gas_counts_all_fees() {
+        let mut risk = Risk {
+            max_gas_per_period: Some(Felt::from(100u8)),
+            ..no_limits()
+        };
+        risk.record(outcome(60, false));
+        assert!(risk.check());
+        risk.record(outcome(40, true));
+        assert!(!risk.check());
+        assert!(risk.tripped().unwrap().starts_with("gas per period"));
+    }
+
+    #[test]
+    fn window_limits_are_lifted_with_old_fees() {
+        let mut risk = Risk {
+            max_loss_per_hour: Some(Felt::from(100u8)),
+            max_loss_per_day: Some(Felt::from(200u8)),
+            ..no_limits()
+        };
+        let two_hours_ago = Instant::now() - 2 * HOUR;
+        risk.fees.push_back((two_hours_ago, outcome(150, true)));
+        // out of the hour, but within the day
+        assert!(risk.check());
+        risk.record(outcome(50, true));
+        assert!(!risk.check());
+        assert!(risk.tripped().unwrap().starts_with("loss per day"));
+        // fees older than a day are dropped
+        risk.fees[0].0 = Instant::now() - DAY - HOUR;
+        assert!(risk.check());
+        assert_eq!(risk.fees.len(), 1);
+        assert_eq!(risk.tripped(), None);
+    }
+
+    #[test]
+    fn unconfirmed_transactions_count_as_reverted_at_the_max_fee() {
+        let mut risk = Risk {
+            max_loss_per_hour: Some(Felt::from(100u8)),
+            ..no_limits()
+        };
+        risk.record(TradeOutcome::unconfirmed(
+            Felt::from(100u8),
+            Felt::from(50u8),
+        ));
+        assert!(!risk.check());
+        assert_eq!(risk.consecutive_reverts, 1);
+    }
+
+    #[test]
+    fn consecutive_reverts_are_reset_by_a_success() {
+        let mut risk = Risk {
+            max_consecutive_reverts: Some(2),
+            ..no_limits()
+        };
+        risk.record(outcome(1, true));
+        risk.record(outcome(1, false));
+        risk.record(outcome(1, true));
+        assert!(risk.check());
+        risk.record(outcome(1, true));
+        assert!(!risk.check());
+        assert!(risk.tripped().unwrap().contains("2 consecutive reverts"));
+    }
+}
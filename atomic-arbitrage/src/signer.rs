@@ -0,0 +1,241 @@
+use async_trait::async_trait;
+use color_eyre::eyre::{bail, Result};
+use serde_json::{json, Value};
+use starknet::{
+    core::{crypto::Signature, types::Felt},
+    providers::Url,
+    signers::{local_wallet, LocalWallet, Signer, SigningKey, VerifyingKey},
+};
+use std::{env, error, fmt, fs};
+use tokio::time::Duration;
+use tracing::info;
+
+const REMOTE_SIGNER_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// The account signer selected by the `SIGNER` env variable:
+/// * `private-key` (default) - `ACCOUNT_PRIVATE_KEY` in plain text
+/// * `keystore` - an encrypted JSON keystore at `KEYSTORE_PATH`, the password is read
+///   from `KEYSTORE_PASSWORD_FILE` or prompted
+/// * `remote` - a signing service at `REMOTE_SIGNER_URL`
+///
+/// With several executor accounts, every variable has the suffix of the account (e.g. `KEYSTORE_PATH_1`),
+/// `SIGNER` without a suffix applies to all accounts.
+pub enum AccountSigner {
+    Local(LocalWallet),
+    Remote(RemoteSigner),
+}
+
+impl AccountSigner {
+    pub fn from_env(suffix: &str) -> Result<Self> {
+        let var = |name: &str| env::var(format!("{name}{suffix}"));
+        let kind = var("SIGNER")
+            .or_else(|_| env::var("SIGNER"))
+            .unwrap_or_else(|_| "private-key".to_string());
+        info!("using {kind} signer for account{suffix}");
+        match kind.as_str() {
+            "private-key" => Ok(Self::Local(LocalWallet::from(
+                SigningKey::from_secret_scalar(Felt::from_hex(&var("ACCOUNT_PRIVATE_KEY")?)?),
+            ))),
+            "keystore" => {
+                let path = var("KEYSTORE_PATH")?;
+                let password = match var("KEYSTORE_PASSWORD_FILE") {
+                    Ok(password_file) => fs::read_to_string(password_file)?
+                        .trim_end_matches(['\r', '\n'])
+                        .to_string(),
+                    Err(_) => rpassword::prompt_password(format!("Password for {path}: "))?,
+                };
+                Ok(Self::Local(LocalWallet::from(SigningKey::from_keystore(
+                    path, &password,
+                )?)))
+            }
+            "remote" => Ok(Self::Remote(RemoteSigner::new(Url::parse(&var(
+                "REMOTE_SIGNER_URL",
+            )?)?)?)),
+            _ => bail!("unsupported signer {kind}, expected one of: private-key, keystore, remote"),
+        }
+    }
+}
+
+#[async_trait]
+impl Signer for AccountSigner {
+    type GetPublicKeyError = SignerError;
+    type SignError = SignerError;
+
+    async fn get_public_key(&self) -> Result<VerifyingKey, Self::GetPublicKeyError> {
+        match self {
+            Self::Local(wallet) => Ok(wallet
+                .get_public_key()
+                .await
+                .unwrap_or_else(|never| match never {})),
+            Self::Remote(signer) => signer.get_public_key().await,
+        }
+    }
+
+    async fn sign_hash(&self, hash: &Felt) -> Result<Signature, Self::SignError> {
+        match self {
+            Self::Local(wallet) => wallet.sign_hash(hash).await.map_err(SignerError::Local),
+            Self::Remote(signer) => signer.sign_hash(hash).await,
+        }
+    }
+}
+
+/// A client of a local signing service with the following HTTP API:
+/// * `GET /public_key` returns `{"public_key": "0x..."}`
+/// * `POST /sign` with `{"hash": "0x..."}` returns `{"r": "0x...", "s": "0x..."}`
+pub struct RemoteSigner {
+    client: reqwest::Client,
+    url: Url,
+}
+
+impl RemoteSigner {
+    pub fn new(mut url: Url) -> Result<Self> {
+        // endpoints are joined to the url, which replaces its last path segment without a trailing slash
+        if !url.path().ends_with('/') {
+            let path = format!("{}/", url.path());
+            url.set_path(&path);
+        }
+        let client = reqwest::Client::builder()
+            .timeout(REMOTE_SIGNER_TIMEOUT)
+            .build()?;
+        Ok(Self { client, url })
+    }
+
+    async fn get_public_key(&self) -> Result<VerifyingKey, SignerError> {
+        let response: Value = self
+            .client
+            .get(self.endpoint("public_key")?)
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(VerifyingKey::from_scalar(felt_field(
+            &response,
+            "public_key",
+        )?))
+    }
+
+    async fn sign_hash(&self, hash: &Felt) -> Result<Signature, SignerError> {
+        let response: Value = self
+            .client
+            .post(self.endpoint("sign")?)
+            .json(&json!({ "hash": format!("{hash:#x}") }))
+            .send()
+            .await?
+            .error_for_status()?
+            .json()
+            .await?;
+        Ok(Signature {
+            r: felt_field(&response, "r")?,
+            s: felt_field(&response, "s")?,
+        })
+    }
+
+    fn endpoint(&self, path: &str) -> Result<Url, SignerError> {
+        self.url
+            .join(path)
+            .map_err(|e| SignerError::Remote(format!("invalid signer url: {e}")))
+    }
+}
+
+fn felt_field(response: &Value, field: &str) -> Result<Felt, SignerError> {
+    response[field]
+        .as_str()
+        .and_then(|value| Felt::from_hex(value).ok())
+        .ok_or_else(|| SignerError::Remote(format!("no {field} in the response: {response}")))
+}
+
+#[derive(Debug)]
+pub enum SignerError {
+    Local(local_wallet::SignError),
+    Remote(String),
+}
+
+impl fmt::Display for SignerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Local(e) => write!(f, "local signer: {e}"),
+            Self::Remote(e) => write!(f, "remote signer: {e}"),
+        }
+    }
+}
+
+impl error::Error for SignerError {}
+
+impl From<reqwest::Error> for SignerError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Remote(e.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    // A signing service stand-in which passes on the request lines and answers with the given bodies
+    fn service(bodies: Vec<Value>) -> (String, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for (stream, body) in listener.incoming().zip(bodies) {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                let mut content_length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some((name, value)) = line.split_once(':') {
+                        if name.eq_ignore_ascii_case("content-length") {
+                            content_length = value.trim().parse().unwrap();
+                        }
+                    }
+                }
+                let mut request_body = vec![0; content_length];
+                reader.read_exact(&mut request_body).unwrap();
+                let body = body.to_string();
+                stream
+                    .write_all(
+                        format!(
+                            "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                            body.len()
+                        )
+                        .as_bytes(),
+                    )
+                    .unwrap();
+                sender.send(request_line.trim_end().to_string()).unwrap();
+            }
+        });
+        (address, receiver)
+    }
+
+    #[tokio::test]
+    async fn endpoints_are_nested_under_the_url_path() {
+        for path in ["/signer", "/signer/"] {
+            let (address, requests) = service(vec![
+                json!({"public_key": "0x123"}),
+                json!({"r": "0x1", "s": "0x2"}),
+            ]);
+            let signer =
+                RemoteSigner::new(Url::parse(&format!("{address}{path}")).unwrap()).unwrap();
+
+            let public_key = signer.get_public_key().await.unwrap();
+            assert_eq!(public_key.scalar(), Felt::from(0x123_u16));
+            assert_eq!(requests.recv().unwrap(), "GET /signer/public_key HTTP/1.1");
+
+            let signature = signer.sign_hash(&Felt::from(7_u8)).await.unwrap();
+            assert_eq!((signature.r, signature.s), (Felt::ONE, Felt::TWO));
+            assert_eq!(requests.recv().unwrap(), "POST /signer/sign HTTP/1.1");
+        }
+    }
+}
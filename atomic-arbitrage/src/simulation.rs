@@ -0,0 +1,167 @@
+use crate::{felt_to_u128, indexer::Swap};
+use color_eyre::eyre::{bail, eyre, Result};
+use ekubo::TokenAmount;
+use starknet::{
+    accounts::{Call, ConnectedAccount},
+    core::{
+        types::{Event, ExecuteInvocation, Felt, FunctionInvocation, TransactionTrace, U256},
+        utils::get_selector_from_name,
+    },
+};
+
+/// The fee of transactions v1 is paid in ETH (the same address on Mainnet and Sepolia)
+pub const FEE_TOKEN_ADDRESS: Felt =
+    Felt::from_hex_unchecked("0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7");
+
+/// Token transfers of the account and Ekubo swaps in a simulated transaction
+pub struct Simulation {
+    pub received: U256,
+    pub sent: U256,
+    /// the fee estimated by the simulation, in WEI for tx v1
+    pub fee: Felt,
+    /// `Swapped` events in the execution order
+    pub swaps: Vec<Swap>,
+}
+
+impl Simulation {
+    /// The balance should rise by at least the minimum profit plus the fee, an overflow is not profitable
+    pub fn is_profitable(&self, min_profit: TokenAmount) -> bool {
+        TokenAmount::from(self.sent)
+            .checked_add(min_profit)
+            .and_then(|minimum| minimum.checked_add(TokenAmount::from(self.fee)))
+            .is_some_and(|minimum| TokenAmount::from(self.received) >= minimum)
+    }
+}
+
+/// Simulates the exact arbitrage calls against the account's block (pending)
+/// and sums up transfers of the token to and from the account.
+/// Swaps are recorded to know the post-swap price of every hop.
+/// Fails if the simulated transaction is reverted.
+pub async fn simulate<A>(
+    account: &A,
+    calls: Vec<Call>,
+    nonce: Felt,
+    max_fee: Felt,
+    token_address: Felt,
+) -> Result<Simulation>
+where
+    A: ConnectedAccount + Sync,
+{
+    let simulated = account
+        .execute_v1(calls)
+        .nonce(nonce)
+        .max_fee(max_fee)
+        .simulate(false, false)
+        .await
+        .map_err(|e| eyre!("Error while simulating arbitrage transaction: {e}"))?;
+    let TransactionTrace::Invoke(trace) = simulated.transaction_trace else {
+        bail!("simulated transaction should be an invoke");
+    };
+    let invocation = match trace.execute_invocation {
+        ExecuteInvocation::Success(invocation) => invocation,
+        ExecuteInvocation::Reverted(reverted) => {
+            bail!(
+                "simulated transaction is reverted: {}",
+                reverted.revert_reason
+            )
+        }
+    };
+    let mut collector = EventCollector {
+        token_address,
+        account_address: account.address(),
+        transfer_selector: get_selector_from_name("Transfer")?,
+        swapped_selector: get_selector_from_name("Swapped")?,
+        received: U256::from(0u8),
+        sent: U256::from(0u8),
+        swaps: vec![],
+    };
+    collector.collect(&invocation)?;
+    // the order of events is global for the transaction while nested calls are visited depth-first
+    collector.swaps.sort_by_key(|(order, _)| *order);
+    Ok(Simulation {
+        received: collector.received,
+        sent: collector.sent,
+        fee: simulated.fee_estimation.overall_fee,
+        swaps: collector.swaps.into_iter().map(|(_, swap)| swap).collect(),
+    })
+}
+
+struct EventCollector {
+    token_address: Felt,
+    account_address: Felt,
+    transfer_selector: Felt,
+    swapped_selector: Felt,
+    received: U256,
+    sent: U256,
+    swaps: Vec<(u64, Swap)>,
+}
+
+impl EventCollector {
+    // Walks the call tree and accounts for every `Transfer` event of the token
+    // involving the account and every `Swapped` event
+    fn collect(&mut self, invocation: &FunctionInvocation) -> Result<()> {
+        for event in &invocation.events {
+            let selector = event.keys.first();
+            if selector == Some(&self.swapped_selector) {
+                self.swaps
+                    .push((event.order, Swap::from_event_data(&event.data)?));
+            }
+            if invocation.contract_address != self.token_address
+                || selector != Some(&self.transfer_selector)
+            {
+                continue;
+            }
+            let (from, to, value) = transfer(&event.keys, &event.data)?;
+            if to == self.account_address {
+                self.received += value;
+            }
+            if from == self.account_address {
+                self.sent += value;
+            }
+        }
+        for call in &invocation.calls {
+            self.collect(call)?;
+        }
+        Ok(())
+    }
+}
+
+/// Transfers of the token to and from the account in the events of a transaction receipt.
+/// Unlike the simulation, the receipt has the fee transfer, so `sent` includes the fee for the fee token.
+pub fn receipt_transfers(
+    events: &[Event],
+    token_address: Felt,
+    account_address: Felt,
+) -> Result<(U256, U256)> {
+    let transfer_selector = get_selector_from_name("Transfer")?;
+    let mut received = U256::from(0u8);
+    let mut sent = U256::from(0u8);
+    for event in events {
+        if event.from_address != token_address || event.keys.first() != Some(&transfer_selector) {
+            continue;
+        }
+        let (from, to, value) = transfer(&event.keys, &event.data)?;
+        if to == account_address {
+            received += value;
+        }
+        if from == account_address {
+            sent += value;
+        }
+    }
+    Ok((received, sent))
+}
+
+fn transfer(keys: &[Felt], data: &[Felt]) -> Result<(Felt, Felt, U256)> {
+    let (from, to, low, high) = match (keys, data) {
+        // Cairo 1 ERC20 with `from` and `to` as keys
+        ([_, from, to], [low, high]) => (*from, *to, *low, *high),
+        // Cairo 0 ERC20
+        ([_], [from, to, low, high]) => (*from, *to, *low, *high),
+        _ => bail!("unexpected Transfer event: keys {keys:?}, data {data:?}"),
+    };
+    Ok((
+        from,
+        to,
+        U256::from_words(felt_to_u128(low)?, felt_to_u128(high)?),
+    ))
+}
@@ -0,0 +1,42 @@
+use color_eyre::eyre::{bail, Result};
+use std::env;
+use tracing_subscriber::{fmt, prelude::*, EnvFilter};
+
+/// Logs are written as text by default or as JSON lines with `LOG_FORMAT=json`.
+/// With the `otlp` feature spans are exported to `OTEL_EXPORTER_OTLP_ENDPOINT` (OTLP over HTTP) if it is set.
+pub fn init() -> Result<()> {
+    let json = match env::var("LOG_FORMAT").as_deref() {
+        Ok("json") => true,
+        Ok("text") | Err(_) => false,
+        Ok(format) => bail!("unsupported log format {format}, expected one of: text, json"),
+    };
+    let registry = tracing_subscriber::registry()
+        .with(json.then(|| fmt::layer().json().flatten_event(true)))
+        .with((!json).then(fmt::layer))
+        .with(EnvFilter::from_default_env());
+    #[cfg(feature = "otlp")]
+    let registry = registry.with(otlp_layer()?);
+    registry.init();
+    Ok(())
+}
+
+#[cfg(feature = "otlp")]
+fn otlp_layer<S>(
+) -> Result<Option<tracing_opentelemetry::OpenTelemetryLayer<S, opentelemetry_sdk::trace::Tracer>>>
+where
+    S: tracing::Subscriber + for<'span> tracing_subscriber::registry::LookupSpan<'span>,
+{
+    use opentelemetry::trace::TracerProvider;
+
+    if env::var("OTEL_EXPORTER_OTLP_ENDPOINT").is_err() {
+        return Ok(None);
+    }
+    // the endpoint and headers are read by the exporter from the standard OTEL_EXPORTER_OTLP_* variables
+    let provider = opentelemetry_otlp::new_pipeline()
+        .tracing()
+        .with_exporter(opentelemetry_otlp::new_exporter().http())
+        .install_batch(opentelemetry_sdk::runtime::TokioCurrentThread)?;
+    Ok(Some(
+        tracing_opentelemetry::layer().with_tracer(provider.tracer("ekubo-atomic-arbitrage")),
+    ))
+}
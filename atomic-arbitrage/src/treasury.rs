@@ -0,0 +1,202 @@
+use crate::{executor::Executor, get_account_balance, wait_for_transaction};
+use color_eyre::eyre::{ensure, Result};
+use starknet::{
+    accounts::{Call, ConnectedAccount},
+    core::{
+        types::{Felt, U256},
+        utils::get_selector_from_name,
+    },
+    providers::Url,
+};
+use std::env;
+use std::iter;
+use tracing::{info, warn};
+
+/// A cold account which keeps balances of executors between the limits:
+/// * an executor below `EXECUTOR_MIN_BALANCE` is topped up to `EXECUTOR_TARGET_BALANCE`
+/// * an executor above `EXECUTOR_MAX_BALANCE` (the accumulated profit) is swept down to `EXECUTOR_TARGET_BALANCE`
+/// * a residual balance of the token at `TREASURY_SWEEP_CONTRACT` is cleared to the cold account
+///
+/// A single transfer is at most `TREASURY_MAX_TRANSFER`.
+/// The cold account is configured as an executor with the `_TREASURY` suffix (e.g. `ACCOUNT_ADDRESS_TREASURY`).
+pub struct Treasury {
+    cold: Executor,
+    min_balance: U256,
+    target_balance: U256,
+    max_balance: Option<U256>,
+    max_transfer: Option<U256>,
+    sweep_contract: Option<Felt>,
+    // transfers sent but not accepted yet, e.g. when the caller timed out the rebalancing
+    in_flight: Vec<Transfer>,
+}
+
+#[derive(Clone, Copy)]
+struct Transfer {
+    from: Felt,
+    tx_hash: Felt,
+}
+
+impl Treasury {
+    /// The treasury is enabled only if `ACCOUNT_ADDRESS_TREASURY` is set
+    pub async fn from_env(provider_url: &Url, chain_id: Felt) -> Result<Option<Self>> {
+        if env::var("ACCOUNT_ADDRESS_TREASURY").is_err() {
+            return Ok(None);
+        }
+        let cold = Executor::from_env("_TREASURY", provider_url, chain_id).await?;
+        let min_balance = amount_from_env("EXECUTOR_MIN_BALANCE")?.unwrap_or(U256::from(0u8));
+        let target_balance = amount_from_env("EXECUTOR_TARGET_BALANCE")?.unwrap_or(min_balance);
+        let max_balance = amount_from_env("EXECUTOR_MAX_BALANCE")?;
+        ensure!(
+            min_balance <= target_balance,
+            "EXECUTOR_TARGET_BALANCE should be at least EXECUTOR_MIN_BALANCE"
+        );
+        if let Some(max_balance) = max_balance {
+            ensure!(
+                target_balance <= max_balance,
+                "EXECUTOR_TARGET_BALANCE should be at most EXECUTOR_MAX_BALANCE"
+            );
+        }
+        let sweep_contract = match env::var("TREASURY_SWEEP_CONTRACT") {
+            Ok(address) => Some(Felt::from_hex(&address)?),
+            Err(_) => None,
+        };
+        Ok(Some(Self {
+            cold,
+            min_balance,
+            target_balance,
+            max_balance,
+            max_transfer: amount_from_env("TREASURY_MAX_TRANSFER")?,
+            sweep_contract,
+            in_flight: Vec::new(),
+        }))
+    }
+
+    /// Balances are re-read before every decision, transfers are sent one by one and awaited.
+    /// A transfer cut off by a timeout of the caller is awaited by the next rebalancing instead of being sent again.
+    pub async fn rebalance(
+        &mut self,
+        executors: &mut [Executor],
+        token_address: Felt,
+    ) -> Result<()> {
+        while let Some(&Transfer { from, tx_hash }) = self.in_flight.first() {
+            info!(
+                tx_hash = %format_args!("{tx_hash:#x}"),
+                "waiting for a treasury transfer of the previous round"
+            );
+            let result = wait_for_transaction(self.cold.account.provider(), tx_hash).await;
+            self.in_flight.remove(0);
+            if let Err(e) = result {
+                warn!(
+                    tx_hash = %format_args!("{tx_hash:#x}"),
+                    error = %format_args!("{e:#}"),
+                    "treasury transfer failed"
+                );
+                // a rejected transaction doesn't consume the nonce
+                iter::once(&mut self.cold)
+                    .chain(executors.iter_mut())
+                    .filter(|sender| sender.address() == from)
+                    .for_each(Executor::reset_nonce);
+            }
+        }
+        let mut cold_balance = self.cold.refresh_balance(token_address).await?;
+        for executor in executors.iter_mut() {
+            // the balance of the scan is stale after its trades and fees
+            let balance = executor.refresh_balance(token_address).await?;
+            if balance < self.min_balance {
+                let amount = self.capped(self.target_balance - balance);
+                if amount > cold_balance {
+                    warn!(
+                        "treasury balance {cold_balance} WEI is not enough to top up account {:#x} by {amount} WEI",
+                        executor.address()
+                    );
+                    continue;
+                }
+                info!(
+                    "topping up account {:#x} by {amount} WEI from treasury",
+                    executor.address()
+                );
+                let call = transfer_call(token_address, executor.address(), amount);
+                transfer(&mut self.in_flight, &mut self.cold, call).await?;
+                cold_balance -= amount;
+            } else if self
+                .max_balance
+                .is_some_and(|max_balance| balance > max_balance)
+            {
+                let amount = self.capped(balance - self.target_balance);
+                info!(
+                    "sweeping {amount} WEI from account {:#x} to treasury",
+                    executor.address()
+                );
+                let call = transfer_call(token_address, self.cold.address(), amount);
+                transfer(&mut self.in_flight, executor, call).await?;
+            } else {
+                continue;
+            }
+            executor.refresh_balance(token_address).await?;
+        }
+        if let Some(contract) = self.sweep_contract {
+            let residual =
+                get_account_balance(token_address, contract, self.cold.account.provider()).await?;
+            if residual > U256::from(0u8) {
+                info!("clearing residual {residual} WEI from {contract:#x} to treasury");
+                let call = clear_call(contract, token_address);
+                transfer(&mut self.in_flight, &mut self.cold, call).await?;
+            }
+        }
+        Ok(())
+    }
+
+    fn capped(&self, amount: U256) -> U256 {
+        match self.max_transfer {
+            Some(max_transfer) => amount.min(max_transfer),
+            None => amount,
+        }
+    }
+}
+
+// The transfer stays in flight until it is accepted or fails
+async fn transfer(in_flight: &mut Vec<Transfer>, sender: &mut Executor, call: Call) -> Result<()> {
+    let tx_hash = sender.broadcast(vec![call]).await?;
+    in_flight.push(Transfer {
+        from: sender.address(),
+        tx_hash,
+    });
+    let result = wait_for_transaction(sender.account.provider(), tx_hash).await;
+    in_flight.retain(|transfer| transfer.tx_hash != tx_hash);
+    if result.is_err() {
+        // a rejected transaction doesn't consume the nonce
+        sender.reset_nonce();
+    }
+    result.map(|_| ())
+}
+
+// amounts are decimal WEI like MIN_PROFIT
+fn amount_from_env(name: &str) -> Result<Option<U256>> {
+    match env::var(name) {
+        Ok(amount) => Ok(Some(U256::from(Felt::from_dec_str(&amount)?))),
+        Err(_) => Ok(None),
+    }
+}
+
+// transfer takes the second argument (amount) as U256
+fn transfer_call(token_address: Felt, recipient: Felt, amount: U256) -> Call {
+    Call {
+        to: token_address,
+        selector: get_selector_from_name("transfer").unwrap(),
+        calldata: vec![
+            recipient,
+            Felt::from(amount.low()),
+            Felt::from(amount.high()),
+        ],
+    }
+}
+
+// clear withdraws the whole balance of the token at the contract to the caller
+// (the same entrypoint as at Ekubo router)
+fn clear_call(contract: Felt, token_address: Felt) -> Call {
+    Call {
+        to: contract,
+        selector: get_selector_from_name("clear").unwrap(),
+        calldata: vec![token_address],
+    }
+}
@@ -0,0 +1,203 @@
+use color_eyre::eyre::{bail, Result, WrapErr};
+use futures::{FutureExt, SinkExt, StreamExt};
+use serde_json::{json, Value};
+use starknet::{
+    core::types::{BlockId, BlockTag, Felt, MaybePendingBlockWithTxHashes},
+    providers::{
+        jsonrpc::{HttpTransport, JsonRpcClient},
+        Provider,
+    },
+};
+use tokio::{
+    net::TcpStream,
+    time::{sleep, Duration},
+};
+use tokio_tungstenite::{connect_async, tungstenite::Message, MaybeTlsStream, WebSocketStream};
+use tracing::{debug, info, warn};
+
+type Subscription = Box<WebSocketStream<MaybeTlsStream<TcpStream>>>;
+
+/// What starts the next scan round
+pub enum Trigger {
+    /// a fixed interval between scans
+    Interval(Duration),
+    /// a new accepted block, from `starknet_subscribeNewHeads` if available or by polling the block number
+    Block {
+        poll_interval: Duration,
+        last_block: u64,
+        subscription: Option<Subscription>,
+    },
+    /// a change of the pending block (a new parent or new transactions)
+    Pending {
+        poll_interval: Duration,
+        last_pending: (Felt, usize),
+    },
+}
+
+impl Trigger {
+    pub async fn new(
+        kind: &str,
+        check_interval: Duration,
+        poll_interval: Duration,
+        ws_url: Option<String>,
+        provider: &JsonRpcClient<HttpTransport>,
+    ) -> Result<Self> {
+        match kind {
+            "interval" => Ok(Self::Interval(check_interval)),
+            "block" => {
+                let subscription = match ws_url {
+                    Some(url) => subscribe_new_heads(&url)
+                        .await
+                        .map_err(|e| warn!(error = %format_args!("{e:#}"), "polling blocks as subscription failed"))
+                        .ok(),
+                    None => None,
+                };
+                Ok(Self::Block {
+                    poll_interval,
+                    last_block: block_number(provider).await?,
+                    subscription,
+                })
+            }
+            "pending" => Ok(Self::Pending {
+                poll_interval,
+                last_pending: pending_block(provider).await?,
+            }),
+            _ => {
+                bail!("unsupported scan trigger {kind}, expected one of: interval, block, pending")
+            }
+        }
+    }
+
+    /// Node errors are logged and polling is retried on the next tick, so waiting never fails
+    pub async fn wait(&mut self, provider: &JsonRpcClient<HttpTransport>) {
+        match self {
+            Self::Interval(interval) => sleep(*interval).await,
+            Self::Block {
+                poll_interval,
+                last_block,
+                subscription,
+            } => {
+                if let Some(ws) = subscription.as_mut() {
+                    match next_head(ws, *last_block).await {
+                        Ok(number) => {
+                            debug!("new block {number}");
+                            *last_block = number;
+                            return;
+                        }
+                        Err(e) => {
+                            warn!(error = %format_args!("{e:#}"), "new heads subscription failed, polling blocks instead");
+                            *subscription = None;
+                        }
+                    }
+                }
+                loop {
+                    match block_number(provider).await {
+                        Ok(number) if number > *last_block => {
+                            debug!("new block {number}");
+                            *last_block = number;
+                            return;
+                        }
+                        Ok(_) => {}
+                        Err(e) => warn!(error = %format_args!("{e:#}"), "polling blocks failed"),
+                    }
+                    sleep(*poll_interval).await;
+                }
+            }
+            Self::Pending {
+                poll_interval,
+                last_pending,
+            } => loop {
+                match pending_block(provider).await {
+                    Ok(pending) if pending != *last_pending => {
+                        debug!(
+                            "pending block on top of {:#x} with {} transactions",
+                            pending.0, pending.1
+                        );
+                        *last_pending = pending;
+                        return;
+                    }
+                    Ok(_) => {}
+                    Err(e) => {
+                        warn!(error = %format_args!("{e:#}"), "polling the pending block failed")
+                    }
+                }
+                sleep(*poll_interval).await;
+            },
+        }
+    }
+}
+
+async fn block_number(provider: &JsonRpcClient<HttpTransport>) -> Result<u64> {
+    provider
+        .block_number()
+        .await
+        .wrap_err("failed to get the latest block number")
+}
+
+// The pending block has no hash, so it is identified by its parent and the number of transactions
+async fn pending_block(provider: &JsonRpcClient<HttpTransport>) -> Result<(Felt, usize)> {
+    match provider
+        .get_block_with_tx_hashes(BlockId::Tag(BlockTag::Pending))
+        .await
+        .wrap_err("failed to get the pending block")?
+    {
+        MaybePendingBlockWithTxHashes::PendingBlock(block) => {
+            Ok((block.parent_hash, block.transactions.len()))
+        }
+        // some nodes return the latest block if there is no pending one
+        MaybePendingBlockWithTxHashes::Block(block) => Ok((block.block_hash, 0)),
+    }
+}
+
+// https://github.com/starkware-libs/starknet-specs/blob/v0.8.0/api/starknet_ws_api.json
+async fn subscribe_new_heads(url: &str) -> Result<Subscription> {
+    let (ws, _) = connect_async(url).await?;
+    let mut ws = Box::new(ws);
+    ws.send(Message::Text(
+        json!({
+            "jsonrpc": "2.0",
+            "id": 1,
+            "method": "starknet_subscribeNewHeads",
+            "params": {},
+        })
+        .to_string(),
+    ))
+    .await?;
+    while let Some(message) = ws.next().await {
+        let Message::Text(text) = message? else {
+            continue;
+        };
+        let response: Value = serde_json::from_str(&text)?;
+        if let Some(error) = response.get("error") {
+            bail!("starknet_subscribeNewHeads is rejected: {error}");
+        }
+        if let Some(id) = response.get("result") {
+            info!("subscribed to new heads at {url}, subscription {id}");
+            return Ok(ws);
+        }
+    }
+    bail!("websocket is closed before subscription")
+}
+
+// Heads queued during a scan are drained down to the newest one, old heads don't start a scan
+async fn next_head(ws: &mut Subscription, last_block: u64) -> Result<u64> {
+    let mut newest = last_block;
+    loop {
+        // queued messages are read without waiting, the next one is awaited only if there is no new head yet
+        let message = match ws.next().now_or_never() {
+            Some(message) => message,
+            None if newest > last_block => return Ok(newest),
+            None => ws.next().await,
+        };
+        let Some(message) = message else {
+            bail!("websocket is closed");
+        };
+        let Message::Text(text) = message? else {
+            continue;
+        };
+        let notification: Value = serde_json::from_str(&text)?;
+        if let Some(number) = notification["params"]["result"]["block_number"].as_u64() {
+            newest = newest.max(number);
+        }
+    }
+}
@@ -1,143 +1,631 @@
-use color_eyre::eyre::Result;
+use color_eyre::eyre::{bail, eyre, Result};
+use core::fmt::Write as _;
+use serde_yaml::Value;
 use std::env;
-use std::fs::{self, OpenOptions};
-use std::io::{self, prelude::*};
-use std::process::Command;
-
-fn replace(path: &str, old: &str, new: &str) -> io::Result<()> {
-    let contents = fs::read_to_string(path)?;
-    let new_contents = contents.replace(old, new);
-    let mut file = OpenOptions::new().write(true).truncate(true).open(path)?;
-    file.write_all(new_contents.as_bytes())?;
-    Ok(())
-}
+use std::fs;
 
 const SPEC_NAME: &str = "openapi.yml";
+const FELT: &str = "starknet_core::types::Felt";
+const AMOUNT: &str = "crate::TokenAmount";
+const METHODS: [&str; 5] = ["get", "post", "put", "patch", "delete"];
 
+// The client is generated from the spec without external tools:
+// * `src/models` - a struct for every object schema and an untagged enum for every `oneOf` schema
+// * `src/apis/default_api.rs` - a function for every operation
+// * `src/apis/configuration.rs` - the configuration with the first server as the base path
+// Credentials of the configuration are sent as declared by `components.securitySchemes`
+// (an api key header, bearer and basic http auth).
+//
+// Vendor extensions:
+// * `x-felt: true` on a string - the value is `Felt` (deserialized from a hex or decimal string)
+// * `x-amount: true` on a string - the value is a non-negative `TokenAmount` (from a hex or decimal string)
+// * `x-hash: true` on an object - the model derives `Eq` and `Hash` (e.g. to be used as a map key)
+//
+// Unsupported constructs (inline objects, parameters other than path and query, etc.) fail the generation.
 fn main() -> Result<()> {
-    if std::env::var("EKUBO_API_REBUILD").is_ok() {
-        let package_version = env!("CARGO_PKG_VERSION");
-        let package_name = env!("CARGO_PKG_NAME");
-        let current_dir = env!("CARGO_MANIFEST_DIR");
-        // https://github.com/OpenAPITools/openapi-generator/blob/master/docs/generators/rust.md
-        Command::new("docker")
-            .arg("run")
-            .arg("--rm")
-            .arg("-v")
-            .arg(&format!("{current_dir}:/local"))
-            .arg("openapitools/openapi-generator-cli")
-            .arg("generate")
-            .arg("-i")
-            .arg(&format!("/local/{SPEC_NAME}"))
-            .arg("-g")
-            .arg("rust")
-            .arg("-o")
-            .arg("/local")
-            .arg("--additional-properties")
-            .arg("library=reqwest")
-            .arg("--additional-properties")
-            .arg(&format!("packageName={package_name}"))
-            .arg("--additional-properties")
-            .arg(&format!("packageVersion={package_version}"))
-            .arg("--additional-properties")
-            .arg("preferUnsignedInt=true")
-            .arg("--additional-properties")
-            .arg("supportMiddleware=true")
-            .arg("--additional-properties")
-            .arg("avoidBoxedModels=true")
-            .status()?;
-
-        // sudo chown -R ${USER}:${USER} ekubo/src/apis/ ekubo/src/models/
-        replace(
-            "src/models/quote.rs",
-            "amount: String",
-            "amount: starknet_core::types::Felt",
-        )?;
-        replace(
-            "src/models/quote.rs",
-            r#"#[serde(rename = "specifiedAmount")]"#,
-            r#"#[serde(rename = "specifiedAmount")]
-        #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]"#,
-        )?;
-        replace(
-            "src/models/quote.rs",
-            r#"#[serde(rename = "amount")]"#,
-            r#"#[serde(rename = "amount")]
-    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]"#,
-        )?;
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed={SPEC_NAME}");
+    println!("cargo:rerun-if-env-changed=EKUBO_API_REBUILD");
+    if env::var("EKUBO_API_REBUILD").is_ok() {
+        let spec: Value = serde_yaml::from_str(&fs::read_to_string(SPEC_NAME)?)?;
+        let generator = Generator::new(&spec)?;
+        generator.models()?;
+        generator.apis()?;
+        generator.configuration()?;
+    }
+    Ok(())
+}
 
-        replace(
-            "src/models/quotes.rs",
-            "total: String",
-            "total: starknet_core::types::Felt",
-        )?;
-        replace(
-            "src/models/quotes.rs",
-            r#"#[serde(rename = "total")]"#,
-            r#"#[serde(rename = "total")]
-    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]"#,
-        )?;
+struct Generator<'spec> {
+    spec: &'spec Value,
+    header: String,
+    version: String,
+    // applies the credentials of the configuration to a request
+    auth: String,
+}
 
-        replace(
-            "src/models/route_node.rs",
-            "sqrt_ratio_limit: String",
-            "sqrt_ratio_limit: starknet_core::types::Felt",
-        )?;
-        replace(
-            "src/models/route_node.rs",
-            r#"#[serde(rename = "sqrt_ratio_limit")]"#,
-            r#"#[serde(rename = "sqrt_ratio_limit")]
-    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]"#,
+impl<'spec> Generator<'spec> {
+    // a single step of `main`, like the other generator methods
+    #[allow(clippy::single_call_fn)]
+    fn new(spec: &'spec Value) -> Result<Self> {
+        let info = &spec["info"];
+        let title = str_field(info, "title")?;
+        let version = str_field(info, "version")?.to_owned();
+        let mut header = format!("/*\n * {title}\n *\n");
+        if let Some(description) = info["description"].as_str() {
+            for line in description.lines() {
+                writeln!(header, " * {line}")?;
+            }
+            header.push_str(" *\n");
+        }
+        writeln!(
+            header,
+            " * The version of the OpenAPI document: {version}\n *\n * Generated by: ekubo/build.rs from {SPEC_NAME}\n */\n"
         )?;
-        replace(
-            "src/models/pool_key.rs",
-            "token0: String",
-            "token0: starknet_core::types::Felt",
+        let auth = auth(spec)?;
+        Ok(Self {
+            spec,
+            header,
+            version,
+            auth,
+        })
+    }
+
+    // a missing `components.schemas` is `Null` in the `serde_yaml::Value` index, not a panic
+    #[allow(clippy::indexing_slicing)]
+    fn models(&self) -> Result<()> {
+        let schemas = self.spec["components"]["schemas"]
+            .as_mapping()
+            .ok_or_else(|| eyre!("no components.schemas in the spec"))?;
+        let mut modules = vec![];
+        for (key, schema) in schemas {
+            let name = key
+                .as_str()
+                .ok_or_else(|| eyre!("schema name should be a string"))?;
+            let code = if schema.get("oneOf").is_some() {
+                self.one_of_model(name, schema)?
+            } else if schema["type"].as_str() == Some("object") {
+                self.object_model(name, schema)?
+            } else {
+                bail!("schema {name}: only objects and oneOf are supported");
+            };
+            modules.push((snake_case(name), camel_case(name), code));
+        }
+        modules.sort();
+        // stale models of removed schemas are deleted
+        for entry in fs::read_dir("src/models")? {
+            let path = entry?.path();
+            if path.extension().is_some_and(|extension| extension == "rs") {
+                fs::remove_file(path)?;
+            }
+        }
+        let mut mod_rs = String::new();
+        for (module, type_name, code) in modules {
+            fs::write(format!("src/models/{module}.rs"), code)?;
+            writeln!(
+                mod_rs,
+                "pub mod {module};\npub use self::{module}::{type_name};"
+            )?;
+        }
+        fs::write("src/models/mod.rs", mod_rs)?;
+        Ok(())
+    }
+
+    fn object_model(&self, name: &str, schema: &Value) -> Result<String> {
+        let type_name = camel_case(name);
+        let description = schema["description"].as_str();
+        let required = required_fields(schema);
+        let mut fields = String::new();
+        let mut params = vec![];
+        let mut inits = String::new();
+        let properties = match schema.get("properties") {
+            Some(properties) => properties
+                .as_mapping()
+                .ok_or_else(|| eyre!("schema {name}: properties should be a mapping"))?
+                .iter()
+                .collect(),
+            None => vec![],
+        };
+        for (key, property_schema) in properties {
+            let property = key
+                .as_str()
+                .ok_or_else(|| eyre!("schema {name}: property name should be a string"))?;
+            let context = format!("{name}.{property}");
+            let field = field_name(property);
+            let rust_type = rust_type(property_schema, &context)?;
+            let felt = rust_type == FELT;
+            if let Some(property_description) = property_schema["description"].as_str() {
+                doc(&mut fields, "    ", property_description)?;
+            }
+            if required.contains(&property) {
+                writeln!(fields, "    #[serde(rename = \"{property}\")]")?;
+                if felt {
+                    writeln!(
+                        fields,
+                        "    #[serde(deserialize_with = \"crate::helpers::deserialize_felt_from_string\")]"
+                    )?;
+                }
+                writeln!(fields, "    pub {field}: {rust_type},")?;
+                params.push(format!("{field}: {rust_type}"));
+                writeln!(inits, "            {field},")?;
+            } else {
+                writeln!(
+                    fields,
+                    "    #[serde(rename = \"{property}\", skip_serializing_if = \"Option::is_none\")]"
+                )?;
+                if felt {
+                    writeln!(
+                        fields,
+                        "    #[serde(default, deserialize_with = \"crate::helpers::deserialize_optional_felt_from_string\")]"
+                    )?;
+                }
+                writeln!(fields, "    pub {field}: Option<{rust_type}>,")?;
+                writeln!(inits, "            {field}: None,")?;
+            }
+        }
+        let derive = if schema["x-hash"].as_bool() == Some(true) {
+            "Clone, Default, Debug, PartialEq, Eq, Hash, Serialize, Deserialize"
+        } else {
+            "Clone, Default, Debug, PartialEq, Serialize, Deserialize"
+        };
+        let mut code = self.header.clone();
+        code.push_str("use crate::models;\nuse serde::{Deserialize, Serialize};\n\n");
+        if let Some(text) = description {
+            doc(&mut code, "", &format!("{type_name} : {text}"))?;
+        }
+        writeln!(code, "#[derive({derive})]\npub struct {type_name} {{")?;
+        code.push_str(&fields);
+        code.push_str("}\n\n");
+        writeln!(code, "impl {type_name} {{")?;
+        if let Some(text) = description {
+            doc(&mut code, "    ", text)?;
+        }
+        writeln!(
+            code,
+            "    pub fn new({}) -> {type_name} {{\n        {type_name} {{",
+            params.join(", ")
         )?;
-        replace(
-            "src/models/pool_key.rs",
-            r#"#[serde(rename = "token0")]"#,
-            r#"#[serde(rename = "token0")]
-    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]"#,
+        code.push_str(&inits);
+        code.push_str("        }\n    }\n}\n\n");
+        Ok(code)
+    }
+
+    fn one_of_model(&self, name: &str, schema: &Value) -> Result<String> {
+        let type_name = camel_case(name);
+        let variants = schema["oneOf"]
+            .as_sequence()
+            .ok_or_else(|| eyre!("schema {name}: oneOf should be a list"))?
+            .iter()
+            .map(|variant| {
+                let reference = variant["$ref"]
+                    .as_str()
+                    .ok_or_else(|| eyre!("schema {name}: oneOf supports only $ref"))?;
+                Ok(camel_case(ref_name(reference)?))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let first = variants
+            .first()
+            .ok_or_else(|| eyre!("schema {name}: oneOf is empty"))?;
+        let mut code = self.header.clone();
+        code.push_str("use crate::models;\nuse serde::{Deserialize, Serialize};\n\n");
+        if let Some(description) = schema["description"].as_str() {
+            doc(&mut code, "", &format!("{type_name} : {description}"))?;
+        }
+        writeln!(
+            code,
+            "#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]\n#[serde(untagged)]\npub enum {type_name} {{"
         )?;
-        replace(
-            "src/models/pool_key.rs",
-            "token1: String",
-            "token1: starknet_core::types::Felt",
+        for variant in &variants {
+            writeln!(code, "    {variant}(models::{variant}),")?;
+        }
+        writeln!(
+            code,
+            "}}\n\nimpl Default for {type_name} {{\n    fn default() -> Self {{\n        Self::{first}(Default::default())\n    }}\n}}\n"
         )?;
-        replace(
-            "src/models/pool_key.rs",
-            r#"#[serde(rename = "token1")]"#,
-            r#"#[serde(rename = "token1")]
-    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]"#,
+        Ok(code)
+    }
+
+    fn apis(&self) -> Result<()> {
+        let paths = self.spec["paths"]
+            .as_mapping()
+            .ok_or_else(|| eyre!("no paths in the spec"))?;
+        let mut code = self.header.clone();
+        code.push_str(
+            "\nuse reqwest;\nuse serde::{Deserialize, Serialize};\nuse crate::{apis::ResponseContent, models};\nuse super::{Error, configuration};\n\n",
+        );
+        for (key, item) in paths {
+            let path = key
+                .as_str()
+                .ok_or_else(|| eyre!("path should be a string"))?;
+            for method in METHODS {
+                if let Some(operation) = item.get(method) {
+                    self.operation(&mut code, path, method, operation)?;
+                }
+            }
+        }
+        fs::write("src/apis/default_api.rs", code)?;
+        Ok(())
+    }
+
+    // a response without a JSON body indexes to a `Null` schema
+    #[allow(clippy::indexing_slicing)]
+    fn operation(
+        &self,
+        code: &mut String,
+        path: &str,
+        method: &str,
+        operation: &Value,
+    ) -> Result<()> {
+        let fn_name = match operation["operationId"].as_str() {
+            Some(id) => snake_case(id),
+            None => {
+                let mut segments: Vec<String> = path
+                    .split('/')
+                    .filter(|segment| !segment.is_empty())
+                    .map(|segment| snake_case(segment.trim_matches(['{', '}'])))
+                    .collect();
+                segments.push(method.to_owned());
+                segments.join("_")
+            }
+        };
+        let error_name = format!("{}Error", camel_case(&fn_name));
+        let responses = operation["responses"]
+            .as_mapping()
+            .ok_or_else(|| eyre!("{fn_name}: no responses"))?;
+        let mut success_type = None;
+        let mut errors = vec![];
+        for (key, response) in responses {
+            let status = key
+                .as_str()
+                .map(str::to_owned)
+                .or_else(|| key.as_u64().map(|number| number.to_string()))
+                .ok_or_else(|| eyre!("{fn_name}: unexpected response status {key:?}"))?;
+            let schema = &response["content"]["application/json"]["schema"];
+            let rust_type = if schema.is_null() {
+                None
+            } else {
+                Some(rust_type(schema, &format!("{fn_name} {status}"))?)
+            };
+            if status.starts_with('2') {
+                if success_type.is_some() {
+                    bail!("{fn_name}: only one success response is supported");
+                }
+                success_type = Some(rust_type.unwrap_or_else(|| "()".to_owned()));
+            } else if let Some(error_type) = rust_type {
+                let variant = if status == "default" {
+                    "DefaultResponse".to_owned()
+                } else {
+                    format!("Status{status}")
+                };
+                errors.push(format!("    {variant}({error_type}),"));
+            } else {
+                // an error without a body is reported as `UnknownValue`
+            }
+        }
+        let success = success_type.ok_or_else(|| eyre!("{fn_name}: no success response"))?;
+
+        let mut params = vec!["configuration: &configuration::Configuration".to_owned()];
+        let mut path_args = String::new();
+        let mut query = String::new();
+        let parameters = match operation.get("parameters") {
+            Some(parameters) => parameters
+                .as_sequence()
+                .ok_or_else(|| eyre!("{fn_name}: parameters should be a list"))?
+                .iter()
+                .collect(),
+            None => vec![],
+        };
+        for parameter in parameters {
+            let name = str_field(parameter, "name")?;
+            let arg = field_name(name);
+            let required = parameter["required"].as_bool() == Some(true);
+            let context = format!("{fn_name}({name})");
+            let schema = &parameter["schema"];
+            let string = schema["type"].as_str() == Some("string") && schema["x-felt"].is_null();
+            let rust_type = if string {
+                "&str".to_owned()
+            } else {
+                rust_type(schema, &context)?
+            };
+            let value = if string {
+                arg.clone()
+            } else {
+                format!("{arg}.to_string()")
+            };
+            match str_field(parameter, "in")? {
+                "path" => {
+                    if !required {
+                        bail!("{context}: path parameters are required");
+                    }
+                    write!(path_args, ", {name}=crate::apis::urlencode({value})")?;
+                    params.push(format!("{arg}: {rust_type}"));
+                }
+                "query" if required => {
+                    writeln!(
+                        query,
+                        "    local_var_req_builder = local_var_req_builder.query(&[(\"{name}\", &{arg}.to_string())]);"
+                    )?;
+                    params.push(format!("{arg}: {rust_type}"));
+                }
+                "query" => {
+                    writeln!(
+                        query,
+                        "    if let Some(ref local_var_str) = {arg} {{\n        local_var_req_builder = local_var_req_builder.query(&[(\"{name}\", &local_var_str.to_string())]);\n    }}"
+                    )?;
+                    params.push(format!("{arg}: Option<{rust_type}>"));
+                }
+                location => bail!("{context}: {location} parameters are not supported"),
+            }
+        }
+
+        write!(
+            code,
+            "\n/// struct for typed errors of method [`{fn_name}`]\n#[derive(Debug, Clone, Serialize, Deserialize)]\n#[serde(untagged)]\npub enum {error_name} {{\n"
         )?;
-        replace(
-            "src/models/pool_key.rs",
-            "fee: String",
-            "fee: starknet_core::types::Felt",
+        for error in &errors {
+            writeln!(code, "{error}")?;
+        }
+        code.push_str("    UnknownValue(serde_json::Value),\n}\n\n\n");
+        if let Some(description) = operation["description"].as_str() {
+            doc(code, "", description)?;
+        }
+        writeln!(
+            code,
+            "pub async fn {fn_name}({}) -> Result<{success}, Error<{error_name}>> {{",
+            params.join(", ")
         )?;
-        replace(
-            "src/models/pool_key.rs",
-            r#"#[serde(rename = "fee")]"#,
-            r#"#[serde(rename = "fee")]
-    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]"#,
+        code.push_str("    let local_var_configuration = configuration;\n\n    let local_var_client = &local_var_configuration.client;\n\n");
+        writeln!(
+            code,
+            "    let local_var_uri_str = format!(\"{{}}{path}\", local_var_configuration.base_path{path_args});"
         )?;
-        replace(
-            "src/models/pool_key.rs",
-            "extension: String",
-            "extension: starknet_core::types::Felt",
+        writeln!(
+            code,
+            "    let mut local_var_req_builder = local_var_client.request(reqwest::Method::{}, local_var_uri_str.as_str());\n",
+            method.to_uppercase()
         )?;
-        replace(
-            "src/models/pool_key.rs",
-            r#"#[serde(rename = "extension")]"#,
-            r#"#[serde(rename = "extension")]
-    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]"#,
+        code.push_str(&query);
+        code.push_str("    if let Some(ref local_var_user_agent) = local_var_configuration.user_agent {\n        local_var_req_builder = local_var_req_builder.header(reqwest::header::USER_AGENT, local_var_user_agent.clone());\n    }\n");
+        // an operation may opt out of the security with `security: []`
+        if operation["security"]
+            .as_sequence()
+            .is_none_or(|security| !security.is_empty())
+        {
+            code.push_str(&self.auth);
+        }
+        code.push('\n');
+        code.push_str("    let local_var_req = local_var_req_builder.build()?;\n    let local_var_resp = local_var_client.execute(local_var_req).await?;\n\n    let local_var_status = local_var_resp.status();\n    let local_var_content = local_var_resp.text().await?;\n\n");
+        code.push_str(
+            "    if !local_var_status.is_client_error() && !local_var_status.is_server_error() {\n",
+        );
+        if success == "()" {
+            code.push_str("        Ok(())\n");
+        } else {
+            code.push_str(
+                "        serde_json::from_str(&local_var_content).map_err(Error::from)\n",
+            );
+        }
+        writeln!(
+            code,
+            "    }} else {{\n        let local_var_entity: Option<{error_name}> = serde_json::from_str(&local_var_content).ok();\n        let local_var_error = ResponseContent {{ status: local_var_status, content: local_var_content, entity: local_var_entity }};\n        Err(Error::ResponseError(local_var_error))\n    }}\n}}\n"
         )?;
+        Ok(())
+    }
+
+    // an empty `servers` list indexes to `Null` and is reported below
+    #[allow(clippy::indexing_slicing)]
+    fn configuration(&self) -> Result<()> {
+        let base_path = self.spec["servers"][0]["url"]
+            .as_str()
+            .ok_or_else(|| eyre!("no servers in the spec"))?;
+        let version = &self.version;
+        let code = format!(
+            r#"{}
+
+#[derive(Debug, Clone)]
+pub struct Configuration {{
+    pub base_path: String,
+    pub user_agent: Option<String>,
+    pub client: reqwest_middleware::ClientWithMiddleware,
+    pub basic_auth: Option<BasicAuth>,
+    pub oauth_access_token: Option<String>,
+    pub bearer_access_token: Option<String>,
+    pub api_key: Option<ApiKey>,
+    // TODO: take an oauth2 token source, similar to the go one
+}}
+
+pub type BasicAuth = (String, Option<String>);
+
+#[derive(Debug, Clone)]
+pub struct ApiKey {{
+    pub prefix: Option<String>,
+    pub key: String,
+}}
 
-        println!("cargo:rerun-if-changed=Cargo.toml");
-        println!("cargo:rerun-if-changed=build.rs");
-        println!("cargo:rerun-if-changed=.openapi-generator-ignore");
-        println!("cargo:rerun-if-changed={current_dir}/{SPEC_NAME}");
+
+impl Configuration {{
+    pub fn new() -> Configuration {{
+        Configuration::default()
+    }}
+}}
+
+impl Default for Configuration {{
+    fn default() -> Self {{
+        Configuration {{
+            base_path: "{base_path}".to_owned(),
+            user_agent: Some("OpenAPI-Generator/{version}/rust".to_owned()),
+            client: reqwest_middleware::ClientBuilder::new(reqwest::Client::new()).build(),
+            basic_auth: None,
+            oauth_access_token: None,
+            bearer_access_token: None,
+            api_key: None,
+
+        }}
+    }}
+}}
+"#,
+            self.header
+        );
+        fs::write("src/apis/configuration.rs", code)?;
+        Ok(())
+    }
+}
+
+// Every security scheme of the spec is applied if its credentials are set in the configuration.
+// Missing `securitySchemes` index to `Null`, and the code is built once for all operations.
+#[allow(clippy::indexing_slicing, clippy::single_call_fn)]
+fn auth(spec: &Value) -> Result<String> {
+    let mut code = String::new();
+    let Some(schemes) = spec["components"]["securitySchemes"].as_mapping() else {
+        return Ok(code);
+    };
+    for (name, scheme) in schemes {
+        let context = format!("security scheme {name:?}");
+        match (str_field(scheme, "type")?, scheme["scheme"].as_str()) {
+            ("apiKey", _) => {
+                if str_field(scheme, "in")? != "header" {
+                    bail!("{context}: only api keys in headers are supported");
+                }
+                let header = str_field(scheme, "name")?;
+                writeln!(
+                    code,
+                    "    if let Some(ref local_var_apikey) = local_var_configuration.api_key {{\n        let local_var_key = local_var_apikey.key.clone();\n        let local_var_value = match local_var_apikey.prefix {{\n            Some(ref local_var_prefix) => format!(\"{{}} {{}}\", local_var_prefix, local_var_key),\n            None => local_var_key,\n        }};\n        local_var_req_builder = local_var_req_builder.header(\"{header}\", local_var_value);\n    }};"
+                )?;
+            }
+            ("http", Some("bearer")) => code.push_str(
+                "    if let Some(ref local_var_token) = local_var_configuration.bearer_access_token {\n        local_var_req_builder = local_var_req_builder.bearer_auth(local_var_token.to_owned());\n    };\n",
+            ),
+            ("http", Some("basic")) => code.push_str(
+                "    if let Some(ref local_var_auth_conf) = local_var_configuration.basic_auth {\n        local_var_req_builder = local_var_req_builder.basic_auth(local_var_auth_conf.0.to_owned(), local_var_auth_conf.1.to_owned());\n    };\n",
+            ),
+            (kind, _) => bail!("{context}: {kind} is not supported"),
+        }
+    }
+    Ok(code)
+}
+
+fn str_field<'value>(value: &'value Value, field: &str) -> Result<&'value str> {
+    value[field]
+        .as_str()
+        .ok_or_else(|| eyre!("{field} should be a string in {value:?}"))
+}
+
+// kept apart so `object_model` reads as the generated struct
+#[allow(clippy::single_call_fn)]
+fn required_fields(schema: &Value) -> Vec<&str> {
+    schema["required"]
+        .as_sequence()
+        .map(|required| required.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default()
+}
+
+fn ref_name(reference: &str) -> Result<&str> {
+    reference
+        .strip_prefix("#/components/schemas/")
+        .ok_or_else(|| eyre!("only local schema references are supported: {reference}"))
+}
+
+fn rust_type(schema: &Value, context: &str) -> Result<String> {
+    if let Some(reference) = schema["$ref"].as_str() {
+        return Ok(format!("models::{}", camel_case(ref_name(reference)?)));
+    }
+    if schema["x-amount"].as_bool() == Some(true) {
+        if schema["type"].as_str() != Some("string") {
+            bail!("{context}: x-amount is supported only for strings");
+        }
+        return Ok(AMOUNT.to_owned());
+    }
+    if schema["x-felt"].as_bool() == Some(true) {
+        if schema["type"].as_str() != Some("string") {
+            bail!("{context}: x-felt is supported only for strings");
+        }
+        return Ok(FELT.to_owned());
+    }
+    match schema["type"].as_str() {
+        Some("string") => Ok("String".to_owned()),
+        Some("integer") => {
+            let unsigned = schema["minimum"]
+                .as_i64()
+                .is_some_and(|minimum| minimum >= 0);
+            let rust_type = match (schema["format"].as_str(), unsigned) {
+                (Some("int64"), true) => "u64",
+                (Some("int64"), false) => "i64",
+                (_, true) => "u32",
+                (_, false) => "i32",
+            };
+            Ok(rust_type.to_owned())
+        }
+        Some("number") => Ok("f64".to_owned()),
+        Some("boolean") => Ok("bool".to_owned()),
+        Some("array") => Ok(format!(
+            "Vec<{}>",
+            rust_type(&schema["items"], &format!("{context}[]"))?
+        )),
+        Some("object") => match schema["additionalProperties"] {
+            Value::Bool(true) => {
+                Ok("std::collections::HashMap<String, serde_json::Value>".to_owned())
+            }
+            Value::Mapping(_) => Ok(format!(
+                "std::collections::HashMap<String, {}>",
+                rust_type(&schema["additionalProperties"], context)?
+            )),
+            Value::Null
+            | Value::Bool(false)
+            | Value::Number(_)
+            | Value::String(_)
+            | Value::Sequence(_)
+            | Value::Tagged(_) => {
+                bail!("{context}: inline objects are not supported, use a $ref")
+            }
+        },
+        _ => bail!("{context}: unsupported schema {schema:?}"),
+    }
+}
+
+fn doc(code: &mut String, indent: &str, text: &str) -> Result<()> {
+    for line in text.trim_end().lines() {
+        writeln!(code, "{indent}/// {}", line.trim_end())?;
     }
     Ok(())
 }
+
+fn field_name(name: &str) -> String {
+    let field = snake_case(name);
+    match field.as_str() {
+        "type" | "ref" | "self" | "match" | "mod" | "move" | "fn" | "impl" | "use" => {
+            format!("r#{field}")
+        }
+        _ => field,
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut snake = String::new();
+    for (i, c) in name.chars().enumerate() {
+        if c.is_ascii_uppercase() {
+            if i > 0 && !snake.ends_with('_') {
+                snake.push('_');
+            }
+            snake.push(c.to_ascii_lowercase());
+        } else if c.is_ascii_alphanumeric() {
+            snake.push(c);
+        } else {
+            snake.push('_');
+        }
+    }
+    snake
+}
+
+fn camel_case(name: &str) -> String {
+    snake_case(name)
+        .split('_')
+        .filter(|part| !part.is_empty())
+        .map(|part| {
+            let mut chars = part.chars();
+            chars
+                .next()
+                .map(|first| format!("{}{}", first.to_ascii_uppercase(), chars.as_str()))
+                .unwrap_or_default()
+        })
+        .collect()
+}
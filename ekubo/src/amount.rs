@@ -0,0 +1,337 @@
+use color_eyre::eyre::{bail, ensure, eyre, Report, Result};
+use core::fmt;
+use core::str::FromStr;
+use num_bigint::BigUint;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use starknet_core::types::{Felt, U256};
+
+const U64_BITS: u32 = u64::BITS;
+
+/// A non-negative amount of a token in its smallest units (e.g. WEI) in the range of Cairo `u256`.
+/// Arithmetic is checked, so a negative difference is `None` instead of a wrapped huge value.
+/// Displayed and serialized as a decimal integer, parsed from a decimal or `0x` hex integer.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct TokenAmount(U256);
+
+impl TokenAmount {
+    #[inline]
+    pub fn zero() -> Self {
+        Self::from_words(0, 0)
+    }
+
+    #[inline]
+    pub fn from_words(low: u128, high: u128) -> Self {
+        Self(U256::from_words(low, high))
+    }
+
+    #[inline]
+    pub fn low(&self) -> u128 {
+        self.0.low()
+    }
+
+    #[inline]
+    pub fn high(&self) -> u128 {
+        self.0.high()
+    }
+
+    #[inline]
+    pub fn is_zero(&self) -> bool {
+        self.low() == 0 && self.high() == 0
+    }
+
+    #[inline]
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        let (low, carry) = self.low().overflowing_add(other.low());
+        let high = self
+            .high()
+            .checked_add(other.high())?
+            .checked_add(u128::from(carry))?;
+        Some(Self::from_words(low, high))
+    }
+
+    #[inline]
+    pub fn checked_sub(self, other: Self) -> Option<Self> {
+        let (low, borrow) = self.low().overflowing_sub(other.low());
+        let high = self
+            .high()
+            .checked_sub(other.high())?
+            .checked_sub(u128::from(borrow))?;
+        Some(Self::from_words(low, high))
+    }
+
+    #[inline]
+    pub fn saturating_sub(self, other: Self) -> Self {
+        self.checked_sub(other).unwrap_or_else(Self::zero)
+    }
+
+    #[inline]
+    #[allow(clippy::big_endian_bytes)]
+    pub fn to_biguint(&self) -> BigUint {
+        let mut bytes = self.high().to_be_bytes().to_vec();
+        bytes.extend(self.low().to_be_bytes());
+        BigUint::from_bytes_be(&bytes)
+    }
+
+    /// `None` if the value doesn't fit `u256`
+    #[inline]
+    pub fn from_biguint(value: &BigUint) -> Option<Self> {
+        if value.bits() > 256 {
+            return None;
+        }
+        let mut words = value.to_u64_digits().into_iter();
+        let mut next = || u128::from(words.next().unwrap_or_default());
+        let low = next() | (next() << U64_BITS);
+        let high = next() | (next() << U64_BITS);
+        Some(Self::from_words(low, high))
+    }
+
+    /// A human-readable amount in whole tokens, e.g. `1.5` for `1500000000000000000` with 18 decimals
+    #[inline]
+    pub fn format_units(&self, decimals: u8) -> String {
+        let digits = self.to_string();
+        let width = usize::from(decimals);
+        if width == 0 {
+            return digits;
+        }
+        // at least one digit of the whole part
+        let padded = format!("{digits:0>width$}", width = width.saturating_add(1));
+        let (whole, fraction) = padded.split_at(padded.len().saturating_sub(width));
+        let significant = fraction.trim_end_matches('0');
+        if significant.is_empty() {
+            whole.to_owned()
+        } else {
+            format!("{whole}.{significant}")
+        }
+    }
+
+    /// The inverse of `format_units`, e.g. `1.5` with 18 decimals is `1500000000000000000`
+    #[inline]
+    pub fn parse_units(value: &str, decimals: u8) -> Result<Self> {
+        let (whole, fraction) = value.split_once('.').unwrap_or((value, ""));
+        let width = usize::from(decimals);
+        ensure!(
+            fraction.len() <= width,
+            "{value} has more than {decimals} decimals"
+        );
+        ensure!(
+            !whole.is_empty() || !fraction.is_empty(),
+            "an amount is expected"
+        );
+        format!("{whole}{fraction:0<width$}").parse()
+    }
+}
+
+impl Default for TokenAmount {
+    #[inline]
+    fn default() -> Self {
+        Self::zero()
+    }
+}
+
+impl fmt::Display for TokenAmount {
+    #[inline]
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl FromStr for TokenAmount {
+    type Err = Report;
+
+    #[inline]
+    fn from_str(s: &str) -> Result<Self> {
+        let value = match s.strip_prefix("0x") {
+            Some(hex) => BigUint::parse_bytes(hex.as_bytes(), 16),
+            None if s.starts_with('-') => bail!("amount {s} is negative"),
+            None => BigUint::parse_bytes(s.as_bytes(), 10),
+        }
+        .ok_or_else(|| eyre!("invalid amount {s}"))?;
+        Self::from_biguint(&value).ok_or_else(|| eyre!("amount {s} is out of u256 range"))
+    }
+}
+
+impl From<U256> for TokenAmount {
+    #[inline]
+    fn from(value: U256) -> Self {
+        Self(value)
+    }
+}
+
+impl From<TokenAmount> for U256 {
+    #[inline]
+    fn from(value: TokenAmount) -> Self {
+        value.0
+    }
+}
+
+// a felt is below 2^252, so it always fits
+impl From<Felt> for TokenAmount {
+    #[inline]
+    fn from(value: Felt) -> Self {
+        Self(U256::from(value))
+    }
+}
+
+impl From<u128> for TokenAmount {
+    #[inline]
+    fn from(value: u128) -> Self {
+        Self::from_words(value, 0)
+    }
+}
+
+// a felt is in the field, so large amounts don't fit
+impl TryFrom<TokenAmount> for Felt {
+    type Error = Report;
+
+    #[inline]
+    fn try_from(value: TokenAmount) -> Result<Self> {
+        let felt = Felt::from(value.to_biguint());
+        ensure!(
+            TokenAmount::from(felt) == value,
+            "amount {value} doesn't fit a felt"
+        );
+        Ok(felt)
+    }
+}
+
+impl Serialize for TokenAmount {
+    #[inline]
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[allow(clippy::missing_trait_methods)]
+impl<'de> Deserialize<'de> for TokenAmount {
+    #[inline]
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        String::deserialize(deserializer)?
+            .parse()
+            .map_err(|e: Report| D::Error::custom(e.to_string()))
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::default_numeric_fallback,
+    clippy::separated_literal_suffix,
+    clippy::assertions_on_result_states
+)]
+mod tests {
+    use super::*;
+
+    const ETH_DECIMALS: u8 = 18;
+
+    #[test]
+    fn formats_whole_tokens() {
+        let amount: TokenAmount = "1500000000000000000".parse().unwrap();
+        assert_eq!(amount.format_units(ETH_DECIMALS), "1.5");
+        assert_eq!(
+            TokenAmount::from(1_u128).format_units(ETH_DECIMALS),
+            "0.000000000000000001"
+        );
+        assert_eq!(TokenAmount::zero().format_units(ETH_DECIMALS), "0");
+        assert_eq!(TokenAmount::from(2_000_000_u128).format_units(6), "2");
+        assert_eq!(TokenAmount::from(1_234_u128).format_units(0), "1234");
+    }
+
+    #[test]
+    fn parses_whole_tokens() {
+        assert_eq!(
+            TokenAmount::parse_units("1.5", ETH_DECIMALS).unwrap(),
+            TokenAmount::from(1_500_000_000_000_000_000_u128)
+        );
+        assert_eq!(
+            TokenAmount::parse_units(".5", 1).unwrap(),
+            TokenAmount::from(5_u128)
+        );
+        assert_eq!(
+            TokenAmount::parse_units("2", 6).unwrap(),
+            TokenAmount::from(2_000_000_u128)
+        );
+        // no rounding: extra decimals are rejected rather than truncated
+        assert!(TokenAmount::parse_units("0.0000001", 6).is_err());
+        assert!(TokenAmount::parse_units(".", 6).is_err());
+        assert!(TokenAmount::parse_units("-1", 6).is_err());
+    }
+
+    #[test]
+    fn format_and_parse_round_trip() {
+        for digits in ["0", "1", "10", "123456789", "1000000000000000001"] {
+            let amount: TokenAmount = digits.parse().unwrap();
+            let formatted = amount.format_units(ETH_DECIMALS);
+            assert_eq!(
+                TokenAmount::parse_units(&formatted, ETH_DECIMALS).unwrap(),
+                amount
+            );
+        }
+    }
+
+    #[test]
+    fn parses_decimal_and_hex() {
+        assert_eq!(
+            "255".parse::<TokenAmount>().unwrap(),
+            TokenAmount::from(255_u128)
+        );
+        assert_eq!(
+            "0xff".parse::<TokenAmount>().unwrap(),
+            TokenAmount::from(255_u128)
+        );
+        assert!("-1".parse::<TokenAmount>().is_err());
+        assert!("1.5".parse::<TokenAmount>().is_err());
+        assert!("".parse::<TokenAmount>().is_err());
+        let max = format!("0x{}", "f".repeat(64));
+        assert_eq!(
+            max.parse::<TokenAmount>().unwrap(),
+            TokenAmount::from_words(u128::MAX, u128::MAX)
+        );
+        let above_max = format!("0x1{}", "0".repeat(64));
+        assert!(above_max.parse::<TokenAmount>().is_err());
+    }
+
+    #[test]
+    fn checked_arithmetic() {
+        let max = TokenAmount::from_words(u128::MAX, u128::MAX);
+        let one = TokenAmount::from(1_u128);
+        assert_eq!(
+            TokenAmount::from(u128::MAX).checked_add(one),
+            Some(TokenAmount::from_words(0, 1))
+        );
+        assert_eq!(max.checked_add(one), None);
+        assert_eq!(
+            TokenAmount::from_words(0, 1).checked_sub(one),
+            Some(TokenAmount::from(u128::MAX))
+        );
+        assert_eq!(TokenAmount::zero().checked_sub(one), None);
+        assert_eq!(one.saturating_sub(max), TokenAmount::zero());
+    }
+
+    #[test]
+    fn converts_big_integers_and_felts() {
+        let amount = TokenAmount::from_words(7, 3);
+        assert_eq!(
+            TokenAmount::from_biguint(&amount.to_biguint()),
+            Some(amount)
+        );
+        assert_eq!(
+            TokenAmount::from_biguint(&(BigUint::from(1_u8) << 256_u32)),
+            None
+        );
+        let felt = Felt::from_hex("0x123456789abcdef").unwrap();
+        assert_eq!(Felt::try_from(TokenAmount::from(felt)).unwrap(), felt);
+        assert!(Felt::try_from(TokenAmount::from_words(u128::MAX, u128::MAX)).is_err());
+    }
+
+    #[test]
+    fn serializes_as_decimal_string() {
+        let amount = TokenAmount::from(1_000_u128);
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "\"1000\"");
+        assert_eq!(
+            serde_json::from_str::<TokenAmount>("\"0x3e8\"").unwrap(),
+            amount
+        );
+        assert!(serde_json::from_str::<TokenAmount>("\"-1\"").is_err());
+    }
+}
@@ -1,11 +1,11 @@
 /*
  * Ekubo API Client
  *
- * Сlient for Ekubo AMM DEX. 
+ * Сlient for Ekubo AMM DEX.
  *
  * The version of the OpenAPI document: 0.1.0
- * 
- * Generated by: https://openapi-generator.tech
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
  */
 
 
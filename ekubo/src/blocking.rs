@@ -0,0 +1,263 @@
+use crate::{models, TokenRegistry};
+use color_eyre::eyre::Result;
+use starknet_core::types::Felt;
+use std::collections::HashMap;
+use tokio::runtime::{Builder, Runtime};
+
+/// The same API as the async `crate::Client` for synchronous code.
+/// Requests are run on an own single-threaded runtime,
+/// so the client panics if it is used from within an async runtime (like `reqwest::blocking`).
+pub struct Client {
+    inner: crate::Client,
+    runtime: Runtime,
+}
+
+impl Client {
+    #[inline]
+    pub fn new(base_path: String, user_agent: String) -> Result<Self> {
+        Self::from_client(crate::Client::new(base_path, user_agent))
+    }
+
+    /// A client made with `crate::Client::builder`
+    #[inline]
+    pub fn from_client(inner: crate::Client) -> Result<Self> {
+        Ok(Self {
+            inner,
+            runtime: Builder::new_current_thread().enable_all().build()?,
+        })
+    }
+
+    /// The same as the deprecated `crate::Client::quote`
+    #[inline]
+    #[deprecated(note = "use `quote_by_address` with `Felt` token addresses")]
+    #[allow(deprecated)]
+    pub fn quote(&self, amount: Felt, token: &str, other_token: &str) -> Result<models::Quote> {
+        self.runtime
+            .block_on(self.inner.quote(amount, token, other_token))
+    }
+
+    /// The same as the deprecated `crate::Client::quotes`
+    #[inline]
+    #[deprecated(note = "use `quotes_by_address` with `Felt` token addresses")]
+    #[allow(deprecated)]
+    pub fn quotes(
+        &self,
+        amount: Felt,
+        token: &str,
+        other_token: &str,
+        max_splits: u8,
+        max_hops: u8,
+    ) -> Result<models::Quotes> {
+        self.runtime.block_on(
+            self.inner
+                .quotes(amount, token, other_token, max_splits, max_hops),
+        )
+    }
+
+    #[inline]
+    pub fn quote_by_address(
+        &self,
+        amount: Felt,
+        token: Felt,
+        other_token: Felt,
+    ) -> Result<models::Quote> {
+        self.runtime
+            .block_on(self.inner.quote_by_address(amount, token, other_token))
+    }
+
+    #[inline]
+    pub fn quotes_by_address(
+        &self,
+        amount: Felt,
+        token: Felt,
+        other_token: Felt,
+        max_splits: u8,
+        max_hops: u8,
+    ) -> Result<models::Quotes> {
+        self.runtime.block_on(self.inner.quotes_by_address(
+            amount,
+            token,
+            other_token,
+            max_splits,
+            max_hops,
+        ))
+    }
+
+    #[inline]
+    pub fn tokens(&self) -> Result<Vec<models::Token>> {
+        self.runtime.block_on(self.inner.tokens())
+    }
+
+    #[inline]
+    pub fn token_registry(&self) -> Result<TokenRegistry> {
+        self.runtime.block_on(self.inner.token_registry())
+    }
+
+    #[inline]
+    pub fn pairs(&self) -> Result<Vec<models::PairStats>> {
+        self.runtime.block_on(self.inner.pairs())
+    }
+
+    #[inline]
+    pub fn pair_pools(&self, token: Felt, other_token: Felt) -> Result<Vec<models::PoolStats>> {
+        self.runtime
+            .block_on(self.inner.pair_pools(token, other_token))
+    }
+
+    #[inline]
+    pub fn price(&self, base_token: Felt, quote_token: Felt) -> Result<models::Price> {
+        self.runtime
+            .block_on(self.inner.price(base_token, quote_token))
+    }
+
+    #[inline]
+    pub fn price_history(
+        &self,
+        base_token: Felt,
+        quote_token: Felt,
+        interval: Option<u32>,
+    ) -> Result<Vec<models::PricePoint>> {
+        self.runtime
+            .block_on(self.inner.price_history(base_token, quote_token, interval))
+    }
+
+    #[inline]
+    pub fn pools(&self) -> Result<Vec<models::PoolState>> {
+        self.runtime.block_on(self.inner.pools())
+    }
+
+    #[inline]
+    pub fn pool_states(&self) -> Result<HashMap<Felt, models::PoolState>> {
+        self.runtime.block_on(self.inner.pool_states())
+    }
+
+    /// Downloads all pools like `crate::Client::pool_state`
+    #[inline]
+    pub fn pool_state(&self, pool_key: &models::PoolKey) -> Result<models::PoolState> {
+        self.runtime.block_on(self.inner.pool_state(pool_key))
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    deprecated,
+    clippy::unwrap_used,
+    clippy::expect_used,
+    clippy::default_numeric_fallback,
+    clippy::assertions_on_result_states,
+    clippy::separated_literal_suffix,
+    clippy::single_call_fn
+)]
+mod tests {
+    use super::*;
+    use crate::TokenAmount;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc;
+    use std::thread;
+
+    const TOKEN: Felt = Felt::from_hex_unchecked("0x1");
+    const OTHER_TOKEN: Felt = Felt::from_hex_unchecked("0x2");
+
+    // An API stand-in which answers every request with the body and passes its request line on
+    fn api(body: String) -> (Client, mpsc::Receiver<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let base_path = format!("http://{}", listener.local_addr().unwrap());
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || {
+            for incoming in listener.incoming() {
+                let mut stream = incoming.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut request_line = String::new();
+                reader.read_line(&mut request_line).unwrap();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    if header.trim_end().is_empty() {
+                        break;
+                    }
+                }
+                write!(
+                    stream,
+                    "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{body}",
+                    body.len()
+                )
+                .unwrap();
+                sender.send(request_line.trim_end().to_owned()).unwrap();
+            }
+        });
+        let client = Client::new(base_path, "ekubo-test".to_owned()).unwrap();
+        (client, receiver)
+    }
+
+    const ROUTE: &str = r#"[{"pool_key":{"token0":"0x1","token1":"0x2","fee":"0x0","tick_spacing":1000,"extension":"0x0"},"sqrt_ratio_limit":"0x1000003f7f1380b75","skip_ahead":0}]"#;
+
+    fn quote_body() -> String {
+        format!(r#"{{"specifiedAmount":"100","amount":"99","route":{ROUTE}}}"#)
+    }
+
+    fn quotes_body() -> String {
+        format!(r#"{{"total":"99","splits":[{}]}}"#, quote_body())
+    }
+
+    #[test]
+    fn quote_passes_symbols_through() {
+        let (client, requests) = api(quote_body());
+        let quote = client.quote(Felt::from(100), "ETH", "USDC").unwrap();
+        assert_eq!(quote.amount, TokenAmount::from(99_u128));
+        assert_eq!(requests.recv().unwrap(), "GET /quote/100/ETH/USDC HTTP/1.1");
+    }
+
+    #[test]
+    fn quote_validates_addresses() {
+        let (client, requests) = api(quote_body());
+        assert!(client.quote(Felt::from(100), "0x1", "0x2").is_ok());
+        assert_eq!(requests.recv().unwrap(), "GET /quote/100/0x1/0x2 HTTP/1.1");
+        // the route doesn't lead to the requested token
+        assert!(client.quote(Felt::from(100), "0x1", "0x3").is_err());
+        assert!(client
+            .quote_by_address(Felt::from(100), TOKEN, OTHER_TOKEN)
+            .is_ok());
+    }
+
+    #[test]
+    fn quotes_pass_limits() {
+        let (client, requests) = api(quotes_body());
+        let quotes = client.quotes(Felt::from(100), "0x1", "0x2", 2, 3).unwrap();
+        assert_eq!(quotes.splits.len(), 1);
+        assert_eq!(
+            requests.recv().unwrap(),
+            "GET /quote/100/0x1/0x2?maxSplits=2&maxHops=3 HTTP/1.1"
+        );
+        assert!(client
+            .quotes_by_address(Felt::from(100), TOKEN, OTHER_TOKEN, 2, 3)
+            .is_ok());
+        // the same response for a smaller amount
+        assert!(client.quotes(Felt::from(50), "0x1", "0x2", 2, 3).is_err());
+        assert!(client.quotes(Felt::from(50), "ETH", "USDC", 2, 3).is_ok());
+    }
+
+    #[test]
+    fn pool_states_by_pool_id() {
+        let pool = |key_hash: &str, fee: &str| {
+            format!(
+                r#"{{"key_hash":"{key_hash}","token0":"0x1","token1":"0x2","fee":"{fee}","tick_spacing":1000,"extension":"0x0","sqrt_ratio":"0x100000000000000000000000000000000","tick":0,"liquidity":"0x10"}}"#
+            )
+        };
+        let (client, requests) = api(format!("[{},{}]", pool("0xa", "0x0"), pool("0xb", "0x1")));
+        let states = client.pool_states().unwrap();
+        assert_eq!(requests.recv().unwrap(), "GET /pools HTTP/1.1");
+        assert_eq!(states.len(), 2);
+        assert_eq!(
+            states
+                .get(&Felt::from_hex_unchecked("0xb"))
+                .map(|state| state.fee),
+            Some(Felt::ONE)
+        );
+        let key = models::PoolKey::new(TOKEN, OTHER_TOKEN, Felt::ZERO, 1000, Felt::ZERO);
+        assert_eq!(
+            client.pool_state(&key).unwrap().key_hash,
+            Felt::from_hex_unchecked("0xa")
+        );
+    }
+}
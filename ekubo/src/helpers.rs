@@ -13,3 +13,22 @@ where
         Felt::from_dec_str(&s).map_err(Error::custom)
     }
 }
+
+// generated for optional `x-felt` properties
+#[allow(dead_code)]
+pub fn deserialize_optional_felt_from_string<'de, D>(
+    deserializer: D,
+) -> Result<Option<Felt>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    Option::<String>::deserialize(deserializer)?
+        .map(|s| {
+            if s.starts_with("0x") {
+                Felt::from_hex(&s).map_err(Error::custom)
+            } else {
+                Felt::from_dec_str(&s).map_err(Error::custom)
+            }
+        })
+        .transpose()
+}
@@ -24,17 +24,98 @@ pub mod apis;
 #[allow(clippy::std_instead_of_alloc)]
 #[allow(clippy::shadow_unrelated)]
 #[allow(clippy::error_impl_error)]
+#[allow(clippy::too_many_arguments)]
 pub mod models;
 
+mod amount;
+#[cfg(feature = "blocking")]
+pub mod blocking;
 mod helpers;
+mod pool;
+mod route;
+pub mod tokens;
+pub mod validation;
 
-use color_eyre::eyre::{bail, Result};
+use color_eyre::eyre::{bail, eyre, Result};
 use starknet_core::types::Felt;
+use std::collections::HashMap;
+
+pub use amount::TokenAmount;
+pub use tokens::{TokenInfo, TokenRegistry};
 
 pub struct Client {
     configuration: apis::configuration::Configuration,
 }
 
+/// Credentials and headers which are sent with every request
+pub struct ClientBuilder {
+    base_path: String,
+    user_agent: Option<String>,
+    api_key: Option<String>,
+    bearer_token: Option<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl ClientBuilder {
+    #[inline]
+    pub fn user_agent(mut self, user_agent: String) -> Self {
+        self.user_agent = Some(user_agent);
+        self
+    }
+
+    /// Sent as the `X-API-Key` header
+    #[inline]
+    pub fn api_key(mut self, key: String) -> Self {
+        self.api_key = Some(key);
+        self
+    }
+
+    /// Sent as `Authorization: Bearer <token>`
+    #[inline]
+    pub fn bearer_token(mut self, token: String) -> Self {
+        self.bearer_token = Some(token);
+        self
+    }
+
+    /// Any other header, e.g. for a proxy
+    #[inline]
+    pub fn header(mut self, name: String, value: String) -> Self {
+        self.headers.push((name, value));
+        self
+    }
+
+    /// Fails on an invalid header name or value
+    #[inline]
+    pub fn build(self) -> Result<Client> {
+        let mut headers = reqwest::header::HeaderMap::new();
+        for (name, value) in self.headers {
+            let mut header_value = reqwest::header::HeaderValue::from_str(&value)
+                .map_err(|e| eyre!("invalid value of header {name}: {e}"))?;
+            // secrets are not printed in debug output
+            header_value.set_sensitive(true);
+            headers.insert(
+                reqwest::header::HeaderName::from_bytes(name.as_bytes())?,
+                header_value,
+            );
+        }
+        let client = reqwest::Client::builder()
+            .default_headers(headers)
+            .build()?;
+        let configuration = apis::configuration::Configuration {
+            base_path: self.base_path,
+            user_agent: self.user_agent,
+            client: reqwest_middleware::ClientBuilder::new(client).build(),
+            basic_auth: None,
+            oauth_access_token: None,
+            bearer_access_token: self.bearer_token,
+            api_key: self
+                .api_key
+                .map(|key| apis::configuration::ApiKey { prefix: None, key }),
+        };
+        Ok(Client { configuration })
+    }
+}
+
 impl Client {
     #[inline]
     pub fn new(base_path: String, user_agent: String) -> Self {
@@ -51,11 +132,95 @@ impl Client {
     }
 
     #[inline]
+    pub fn builder(base_path: String) -> ClientBuilder {
+        ClientBuilder {
+            base_path,
+            user_agent: None,
+            api_key: None,
+            bearer_token: None,
+            headers: vec![],
+        }
+    }
+
+    /// Tokens are passed to the API as they are, like before `quote_by_address`:
+    /// the response is validated only if both tokens are `0x` addresses
+    #[inline]
+    #[deprecated(note = "use `quote_by_address` with `Felt` token addresses")]
     pub async fn quote(
         &self,
         amount: Felt,
         token: &str,
         other_token: &str,
+    ) -> Result<models::Quote> {
+        let quote = self.request_quote(amount, token, other_token).await?;
+        if let Some((token_address, other_address)) = addresses(token, other_token) {
+            validation::validate_quote(&quote, amount, token_address, other_address)?;
+        }
+        Ok(quote)
+    }
+
+    /// Tokens are passed to the API as they are, like before `quotes_by_address`:
+    /// the response is validated only if both tokens are `0x` addresses
+    #[inline]
+    #[deprecated(note = "use `quotes_by_address` with `Felt` token addresses")]
+    pub async fn quotes(
+        &self,
+        amount: Felt,
+        token: &str,
+        other_token: &str,
+        max_splits: u8,
+        max_hops: u8,
+    ) -> Result<models::Quotes> {
+        let quotes = self
+            .request_quotes(amount, token, other_token, max_splits, max_hops)
+            .await?;
+        if let Some((token_address, other_address)) = addresses(token, other_token) {
+            validation::validate_quotes(&quotes, amount, token_address, other_address)?;
+        }
+        Ok(quotes)
+    }
+
+    #[inline]
+    pub async fn quote_by_address(
+        &self,
+        amount: Felt,
+        token: Felt,
+        other_token: Felt,
+    ) -> Result<models::Quote> {
+        let quote = self
+            .request_quote(amount, &canonical_hex(token), &canonical_hex(other_token))
+            .await?;
+        validation::validate_quote(&quote, amount, token, other_token)?;
+        Ok(quote)
+    }
+
+    #[inline]
+    pub async fn quotes_by_address(
+        &self,
+        amount: Felt,
+        token: Felt,
+        other_token: Felt,
+        max_splits: u8,
+        max_hops: u8,
+    ) -> Result<models::Quotes> {
+        let quotes = self
+            .request_quotes(
+                amount,
+                &canonical_hex(token),
+                &canonical_hex(other_token),
+                max_splits,
+                max_hops,
+            )
+            .await?;
+        validation::validate_quotes(&quotes, amount, token, other_token)?;
+        Ok(quotes)
+    }
+
+    async fn request_quote(
+        &self,
+        amount: Felt,
+        token: &str,
+        other_token: &str,
     ) -> Result<models::Quote> {
         match apis::default_api::quote_amount_token_other_token_get(
             &self.configuration,
@@ -72,8 +237,7 @@ impl Client {
         }
     }
 
-    #[inline]
-    pub async fn quotes(
+    async fn request_quotes(
         &self,
         amount: Felt,
         token: &str,
@@ -95,4 +259,118 @@ impl Client {
             _ => bail!("quote should return a few Quotes when params are provided"),
         }
     }
+
+    #[inline]
+    pub async fn tokens(&self) -> Result<Vec<models::Token>> {
+        Ok(apis::default_api::tokens_get(&self.configuration).await?)
+    }
+
+    /// The listed tokens by address
+    #[inline]
+    pub async fn token_registry(&self) -> Result<TokenRegistry> {
+        TokenRegistry::from_tokens(self.tokens().await?)
+    }
+
+    /// Top pairs with their 24h volume, fees and TVL
+    #[inline]
+    pub async fn pairs(&self) -> Result<Vec<models::PairStats>> {
+        Ok(apis::default_api::overview_pairs_get(&self.configuration)
+            .await?
+            .top_pairs)
+    }
+
+    /// Pools of a pair with their 24h volume, fees and TVL
+    #[inline]
+    pub async fn pair_pools(
+        &self,
+        token: Felt,
+        other_token: Felt,
+    ) -> Result<Vec<models::PoolStats>> {
+        Ok(apis::default_api::pair_token_a_token_b_pools_get(
+            &self.configuration,
+            &canonical_hex(token),
+            &canonical_hex(other_token),
+        )
+        .await?
+        .top_pools)
+    }
+
+    #[inline]
+    pub async fn price(&self, base_token: Felt, quote_token: Felt) -> Result<models::Price> {
+        Ok(apis::default_api::price_base_token_quote_token_get(
+            &self.configuration,
+            &canonical_hex(base_token),
+            &canonical_hex(quote_token),
+        )
+        .await?)
+    }
+
+    /// Volume weighted average prices over intervals of `interval` seconds (the API default if `None`)
+    #[inline]
+    pub async fn price_history(
+        &self,
+        base_token: Felt,
+        quote_token: Felt,
+        interval: Option<u32>,
+    ) -> Result<Vec<models::PricePoint>> {
+        Ok(apis::default_api::price_base_token_quote_token_history_get(
+            &self.configuration,
+            &canonical_hex(base_token),
+            &canonical_hex(quote_token),
+            interval,
+        )
+        .await?
+        .data)
+    }
+
+    /// The current state of all pools
+    #[inline]
+    pub async fn pools(&self) -> Result<Vec<models::PoolState>> {
+        Ok(apis::default_api::pools_get(&self.configuration).await?)
+    }
+
+    /// The current state of all pools by pool id (`PoolKey::pool_id`), to look up many pools with one request
+    #[inline]
+    pub async fn pool_states(&self) -> Result<HashMap<Felt, models::PoolState>> {
+        Ok(self
+            .pools()
+            .await?
+            .into_iter()
+            .map(|pool| (pool.key_hash, pool))
+            .collect())
+    }
+
+    /// The current state of the pool with the key.
+    /// The API has no endpoint for a single pool, so every call downloads the whole `/pools` list,
+    /// use `pool_states` to look up pools in a loop
+    #[inline]
+    pub async fn pool_state(&self, pool_key: &models::PoolKey) -> Result<models::PoolState> {
+        self.pools()
+            .await?
+            .into_iter()
+            .find(|pool| {
+                pool.token0 == pool_key.token0
+                    && pool.token1 == pool_key.token1
+                    && pool.fee == pool_key.fee
+                    && pool.tick_spacing == pool_key.tick_spacing
+                    && pool.extension == pool_key.extension
+            })
+            .ok_or_else(|| eyre!("pool {pool_key:?} is not found"))
+    }
+}
+
+// Token arguments of the deprecated quote methods may be symbols, which are not validated
+fn addresses(token: &str, other_token: &str) -> Option<(Felt, Felt)> {
+    let address = |value: &str| {
+        Felt::from_hex(value)
+            .ok()
+            .filter(|_| value.starts_with("0x"))
+    };
+    address(token).zip(address(other_token))
+}
+
+/// Lowercase `0x`-prefixed hex without leading zeros, the same address is always requested the same way
+#[inline]
+pub fn canonical_hex(address: Felt) -> String {
+    format!("{address:#x}")
 }
@@ -1,7 +1,23 @@
 pub mod error_response;
 pub use self::error_response::ErrorResponse;
+pub mod pair_pools;
+pub use self::pair_pools::PairPools;
+pub mod pair_stats;
+pub use self::pair_stats::PairStats;
+pub mod pairs_overview;
+pub use self::pairs_overview::PairsOverview;
 pub mod pool_key;
 pub use self::pool_key::PoolKey;
+pub mod pool_state;
+pub use self::pool_state::PoolState;
+pub mod pool_stats;
+pub use self::pool_stats::PoolStats;
+pub mod price;
+pub use self::price::Price;
+pub mod price_history;
+pub use self::price_history::PriceHistory;
+pub mod price_point;
+pub use self::price_point::PricePoint;
 pub mod quote;
 pub use self::quote::Quote;
 pub mod quote_response;
@@ -10,3 +26,5 @@ pub mod quotes;
 pub use self::quotes::Quotes;
 pub mod route_node;
 pub use self::route_node::RouteNode;
+pub mod token;
+pub use self::token::Token;
@@ -0,0 +1,27 @@
+/*
+ * Ekubo API Client
+ *
+ * Сlient for Ekubo AMM DEX.
+ *
+ * The version of the OpenAPI document: 0.1.0
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PairPools {
+    #[serde(rename = "topPools")]
+    pub top_pools: Vec<models::PoolStats>,
+}
+
+impl PairPools {
+    pub fn new(top_pools: Vec<models::PoolStats>) -> PairPools {
+        PairPools {
+            top_pools,
+        }
+    }
+}
+
@@ -0,0 +1,27 @@
+/*
+ * Ekubo API Client
+ *
+ * Сlient for Ekubo AMM DEX.
+ *
+ * The version of the OpenAPI document: 0.1.0
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PairsOverview {
+    #[serde(rename = "topPairs")]
+    pub top_pairs: Vec<models::PairStats>,
+}
+
+impl PairsOverview {
+    pub fn new(top_pairs: Vec<models::PairStats>) -> PairsOverview {
+        PairsOverview {
+            top_pairs,
+        }
+    }
+}
+
@@ -1,18 +1,18 @@
 /*
  * Ekubo API Client
  *
- * Сlient for Ekubo AMM DEX. 
+ * Сlient for Ekubo AMM DEX.
  *
  * The version of the OpenAPI document: 0.1.0
- * 
- * Generated by: https://openapi-generator.tech
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
  */
 
 use crate::models;
 use serde::{Deserialize, Serialize};
 
 /// PoolKey : The composite key identifier for a pool in Ekubo
-#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+#[derive(Clone, Default, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub struct PoolKey {
     /// Address of token0 ERC20 contract
     #[serde(rename = "token0")]
@@ -0,0 +1,65 @@
+/*
+ * Ekubo API Client
+ *
+ * Сlient for Ekubo AMM DEX.
+ *
+ * The version of the OpenAPI document: 0.1.0
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+/// PoolState : The current state of a pool
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PoolState {
+    /// Pool id as computed by Ekubo Core
+    #[serde(rename = "key_hash")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub key_hash: starknet_core::types::Felt,
+    #[serde(rename = "token0")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub token0: starknet_core::types::Felt,
+    #[serde(rename = "token1")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub token1: starknet_core::types::Felt,
+    /// Size of fee, Q0.128
+    #[serde(rename = "fee")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub fee: starknet_core::types::Felt,
+    #[serde(rename = "tick_spacing")]
+    pub tick_spacing: i32,
+    #[serde(rename = "extension")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub extension: starknet_core::types::Felt,
+    /// The current sqrt price, Q64.128
+    #[serde(rename = "sqrt_ratio")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub sqrt_ratio: starknet_core::types::Felt,
+    /// The current tick
+    #[serde(rename = "tick")]
+    pub tick: i32,
+    /// The current active liquidity
+    #[serde(rename = "liquidity")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub liquidity: starknet_core::types::Felt,
+}
+
+impl PoolState {
+    /// The current state of a pool
+    pub fn new(key_hash: starknet_core::types::Felt, token0: starknet_core::types::Felt, token1: starknet_core::types::Felt, fee: starknet_core::types::Felt, tick_spacing: i32, extension: starknet_core::types::Felt, sqrt_ratio: starknet_core::types::Felt, tick: i32, liquidity: starknet_core::types::Felt) -> PoolState {
+        PoolState {
+            key_hash,
+            token0,
+            token1,
+            fee,
+            tick_spacing,
+            extension,
+            sqrt_ratio,
+            tick,
+            liquidity,
+        }
+    }
+}
+
@@ -0,0 +1,64 @@
+/*
+ * Ekubo API Client
+ *
+ * Сlient for Ekubo AMM DEX.
+ *
+ * The version of the OpenAPI document: 0.1.0
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+/// PoolStats : Volume, fees and TVL of a single pool of a pair (amounts are in the smallest units of a token)
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PoolStats {
+    /// Size of fee, Q0.128
+    #[serde(rename = "fee")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub fee: starknet_core::types::Felt,
+    #[serde(rename = "tick_spacing")]
+    pub tick_spacing: i32,
+    #[serde(rename = "extension")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub extension: starknet_core::types::Felt,
+    #[serde(rename = "volume0_24h")]
+    pub volume0_24h: crate::TokenAmount,
+    #[serde(rename = "volume1_24h")]
+    pub volume1_24h: crate::TokenAmount,
+    #[serde(rename = "fees0_24h")]
+    pub fees0_24h: crate::TokenAmount,
+    #[serde(rename = "fees1_24h")]
+    pub fees1_24h: crate::TokenAmount,
+    #[serde(rename = "tvl0_total")]
+    pub tvl0_total: crate::TokenAmount,
+    #[serde(rename = "tvl1_total")]
+    pub tvl1_total: crate::TokenAmount,
+    /// A signed decimal change of TVL of token0
+    #[serde(rename = "tvl0_delta_24h")]
+    pub tvl0_delta_24h: String,
+    /// A signed decimal change of TVL of token1
+    #[serde(rename = "tvl1_delta_24h")]
+    pub tvl1_delta_24h: String,
+}
+
+impl PoolStats {
+    /// Volume, fees and TVL of a single pool of a pair (amounts are in the smallest units of a token)
+    pub fn new(fee: starknet_core::types::Felt, tick_spacing: i32, extension: starknet_core::types::Felt, volume0_24h: crate::TokenAmount, volume1_24h: crate::TokenAmount, fees0_24h: crate::TokenAmount, fees1_24h: crate::TokenAmount, tvl0_total: crate::TokenAmount, tvl1_total: crate::TokenAmount, tvl0_delta_24h: String, tvl1_delta_24h: String) -> PoolStats {
+        PoolStats {
+            fee,
+            tick_spacing,
+            extension,
+            volume0_24h,
+            volume1_24h,
+            fees0_24h,
+            fees1_24h,
+            tvl0_total,
+            tvl1_total,
+            tvl0_delta_24h,
+            tvl1_delta_24h,
+        }
+    }
+}
+
@@ -0,0 +1,34 @@
+/*
+ * Ekubo API Client
+ *
+ * Сlient for Ekubo AMM DEX.
+ *
+ * The version of the OpenAPI document: 0.1.0
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+/// Price : The latest price of a base token in a quote token
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Price {
+    /// A decimal price adjusted for decimals of both tokens
+    #[serde(rename = "price")]
+    pub price: String,
+    /// RFC 3339 time of the price
+    #[serde(rename = "timestamp")]
+    pub timestamp: String,
+}
+
+impl Price {
+    /// The latest price of a base token in a quote token
+    pub fn new(price: String, timestamp: String) -> Price {
+        Price {
+            price,
+            timestamp,
+        }
+    }
+}
+
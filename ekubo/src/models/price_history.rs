@@ -0,0 +1,27 @@
+/*
+ * Ekubo API Client
+ *
+ * Сlient for Ekubo AMM DEX.
+ *
+ * The version of the OpenAPI document: 0.1.0
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PriceHistory {
+    #[serde(rename = "data")]
+    pub data: Vec<models::PricePoint>,
+}
+
+impl PriceHistory {
+    pub fn new(data: Vec<models::PricePoint>) -> PriceHistory {
+        PriceHistory {
+            data,
+        }
+    }
+}
+
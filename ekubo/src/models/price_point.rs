@@ -0,0 +1,32 @@
+/*
+ * Ekubo API Client
+ *
+ * Сlient for Ekubo AMM DEX.
+ *
+ * The version of the OpenAPI document: 0.1.0
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct PricePoint {
+    /// Unix time in seconds of the start of the interval
+    #[serde(rename = "timestamp")]
+    pub timestamp: i64,
+    /// The volume weighted average price over the interval
+    #[serde(rename = "vwap")]
+    pub vwap: f64,
+}
+
+impl PricePoint {
+    pub fn new(timestamp: i64, vwap: f64) -> PricePoint {
+        PricePoint {
+            timestamp,
+            vwap,
+        }
+    }
+}
+
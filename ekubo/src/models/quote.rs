@@ -1,11 +1,11 @@
 /*
  * Ekubo API Client
  *
- * Сlient for Ekubo AMM DEX. 
+ * Сlient for Ekubo AMM DEX.
  *
  * The version of the OpenAPI document: 0.1.0
- * 
- * Generated by: https://openapi-generator.tech
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
  */
 
 use crate::models;
@@ -14,14 +14,13 @@ use serde::{Deserialize, Serialize};
 /// Quote : The suggested route(s) to get the best price
 #[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
 pub struct Quote {
-    /// An input amount
+    /// The specified amount of the request (negative for an exact output, so it is a signed felt)
     #[serde(rename = "specifiedAmount")]
-        #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
     pub specified_amount: starknet_core::types::Felt,
-    /// The calculated amount for the quote
+    /// The calculated amount for the quote, non-negative
     #[serde(rename = "amount")]
-    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
-    pub amount: starknet_core::types::Felt,
+    pub amount: crate::TokenAmount,
     /// The list of pool keys through which to swap
     #[serde(rename = "route")]
     pub route: Vec<models::RouteNode>,
@@ -29,7 +28,7 @@ pub struct Quote {
 
 impl Quote {
     /// The suggested route(s) to get the best price
-    pub fn new(specified_amount: starknet_core::types::Felt, amount: starknet_core::types::Felt, route: Vec<models::RouteNode>) -> Quote {
+    pub fn new(specified_amount: starknet_core::types::Felt, amount: crate::TokenAmount, route: Vec<models::RouteNode>) -> Quote {
         Quote {
             specified_amount,
             amount,
@@ -0,0 +1,50 @@
+/*
+ * Ekubo API Client
+ *
+ * Сlient for Ekubo AMM DEX.
+ *
+ * The version of the OpenAPI document: 0.1.0
+ *
+ * Generated by: ekubo/build.rs from openapi.yml
+ */
+
+use crate::models;
+use serde::{Deserialize, Serialize};
+
+/// Token : A token listed by Ekubo
+#[derive(Clone, Default, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Token {
+    #[serde(rename = "name")]
+    pub name: String,
+    #[serde(rename = "symbol")]
+    pub symbol: String,
+    #[serde(rename = "decimals")]
+    pub decimals: u32,
+    /// Address of the ERC20 contract
+    #[serde(rename = "l2_token_address")]
+    #[serde(deserialize_with = "crate::helpers::deserialize_felt_from_string")]
+    pub l2_token_address: starknet_core::types::Felt,
+    /// A hint for sorting tokens in a list
+    #[serde(rename = "sort_order", skip_serializing_if = "Option::is_none")]
+    pub sort_order: Option<i32>,
+    #[serde(rename = "total_supply", skip_serializing_if = "Option::is_none")]
+    pub total_supply: Option<crate::TokenAmount>,
+    #[serde(rename = "logo_url", skip_serializing_if = "Option::is_none")]
+    pub logo_url: Option<String>,
+}
+
+impl Token {
+    /// A token listed by Ekubo
+    pub fn new(name: String, symbol: String, decimals: u32, l2_token_address: starknet_core::types::Felt) -> Token {
+        Token {
+            name,
+            symbol,
+            decimals,
+            l2_token_address,
+            sort_order: None,
+            total_supply: None,
+            logo_url: None,
+        }
+    }
+}
+
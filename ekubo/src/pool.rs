@@ -0,0 +1,179 @@
+// helpers of the generated models live next to them
+#![allow(clippy::multiple_inherent_impl)]
+
+use crate::{models, TokenAmount};
+use num_bigint::BigUint;
+use starknet_core::types::Felt;
+use starknet_crypto::poseidon_hash_many;
+
+// rounds to 2^128 exactly
+#[allow(clippy::as_conversions, clippy::cast_precision_loss)]
+const Q128: f64 = u128::MAX as f64;
+const BPS: f64 = 10_000.0;
+const PERCENT_SCALE: u32 = 1_000_000;
+const ONE: u8 = 1;
+const TICK_BASE: f64 = 1.000_001;
+
+impl models::PoolKey {
+    /// The id of the pool in Ekubo Core (`PoolKey::to_id`): the Poseidon hash of the serialized key.
+    /// The same as for on-chain events, so routes of the API can be joined with indexed pools.
+    #[inline]
+    pub fn pool_id(&self) -> Felt {
+        poseidon_hash_many(&[
+            self.token0,
+            self.token1,
+            self.fee,
+            Felt::from(self.tick_spacing),
+            self.extension,
+        ])
+    }
+
+    /// The fee as a `u128` Q0.128 fraction, a fee above `u128` (an invalid key) is the maximum
+    #[inline]
+    pub fn fee_q128(&self) -> u128 {
+        let fee = TokenAmount::from(self.fee);
+        if fee.high() == 0 {
+            fee.low()
+        } else {
+            u128::MAX
+        }
+    }
+
+    /// The fee as a decimal fraction of the input, e.g. `0.003` for a 0.3% pool
+    #[inline]
+    #[allow(
+        clippy::as_conversions,
+        clippy::cast_precision_loss,
+        clippy::float_arithmetic
+    )]
+    pub fn fee_decimal(&self) -> f64 {
+        self.fee_q128() as f64 / Q128
+    }
+
+    /// The fee in basis points, e.g. `30` for a 0.3% pool
+    #[inline]
+    #[allow(clippy::float_arithmetic)]
+    pub fn fee_bps(&self) -> f64 {
+        self.fee_decimal() * BPS
+    }
+
+    /// The fee in percent with up to 4 decimals (rounded), e.g. `0.05` for a 0.05% pool
+    #[inline]
+    #[allow(clippy::arithmetic_side_effects)]
+    pub fn fee_percent(&self) -> String {
+        let half = BigUint::from(ONE) << (u128::BITS - 1);
+        let scaled = (BigUint::from(self.fee_q128()) * PERCENT_SCALE + half) >> u128::BITS;
+        TokenAmount::from_biguint(&scaled)
+            .map_or_else(|| "?".to_owned(), |percent| percent.format_units(4))
+    }
+
+    /// The relative price step between initializable ticks, e.g. about `0.001` (0.1%) for the tick spacing 1000,
+    /// as every tick moves the price by the factor of 1.000001
+    #[inline]
+    #[allow(clippy::float_arithmetic)]
+    pub fn price_granularity(&self) -> f64 {
+        TICK_BASE.powi(self.tick_spacing) - 1.0
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::default_numeric_fallback,
+    clippy::float_arithmetic,
+    clippy::float_cmp,
+    clippy::arithmetic_side_effects,
+    clippy::separated_literal_suffix,
+    clippy::single_call_fn
+)]
+mod tests {
+    use super::*;
+    use starknet_crypto::poseidon_permute_comp;
+
+    // 0.05% as a Q0.128 fraction, the fee of the ETH/USDC pools
+    const FEE_5_BPS: Felt = Felt::from_hex_unchecked("0x20c49ba5e353f80000000000000000");
+
+    fn pool_key(fee: Felt, tick_spacing: i32) -> models::PoolKey {
+        models::PoolKey::new(Felt::ONE, Felt::TWO, fee, tick_spacing, Felt::ZERO)
+    }
+
+    // `PoseidonTrait::new().update_with(pool_key).finalize()` of Cairo:
+    // felts are absorbed in pairs into the first two elements of the state, then `1` pads the rest
+    fn cairo_poseidon(values: &[Felt]) -> Felt {
+        let [mut s0, mut s1, mut s2] = [Felt::ZERO; 3];
+        let mut remaining = values;
+        while let Some((&[first, second], rest)) = remaining.split_first_chunk() {
+            [s0, s1, s2] = permute([s0 + first, s1 + second, s2]);
+            remaining = rest;
+        }
+        let [hash, _, _] = match remaining.first() {
+            Some(&last) => permute([s0 + last, s1 + Felt::ONE, s2]),
+            None => permute([s0 + Felt::ONE, s1, s2]),
+        };
+        hash
+    }
+
+    fn permute(mut state: [Felt; 3]) -> [Felt; 3] {
+        poseidon_permute_comp(&mut state);
+        state
+    }
+
+    #[test]
+    fn pool_id_is_the_hash_of_the_serialized_key() {
+        // no on-chain pool id can be fetched offline, so the id is checked against the Cairo sponge
+        let eth =
+            Felt::from_hex("0x049d36570d4e46f48e99674bd3fcc84644ddd6b96f7c741b1562b82f9e004dc7")
+                .unwrap();
+        let usdc =
+            Felt::from_hex("0x053c91253bc9682c04929ca02ed00b3e423f6710d2ee7e0d5ebb06f3ecf368a8")
+                .unwrap();
+        let key = models::PoolKey::new(eth, usdc, FEE_5_BPS, 1000, Felt::ZERO);
+        assert_eq!(
+            key.pool_id(),
+            cairo_poseidon(&[eth, usdc, FEE_5_BPS, Felt::from(1000), Felt::ZERO])
+        );
+        // every field is a part of the id
+        assert_ne!(key.pool_id(), pool_key(FEE_5_BPS, 1000).pool_id());
+        assert_ne!(
+            key.pool_id(),
+            models::PoolKey::new(eth, usdc, FEE_5_BPS, 200, Felt::ZERO).pool_id()
+        );
+        assert_ne!(
+            key.pool_id(),
+            models::PoolKey::new(eth, usdc, FEE_5_BPS, 1000, Felt::ONE).pool_id()
+        );
+    }
+
+    #[test]
+    fn fee_in_percent_and_bps() {
+        let key = pool_key(FEE_5_BPS, 1000);
+        assert_eq!(key.fee_q128(), 0x20c49ba5e353f80000000000000000);
+        assert_eq!(key.fee_percent(), "0.05");
+        assert!((key.fee_bps() - 5.0).abs() < 1e-9);
+        assert!((key.fee_decimal() - 0.0005).abs() < 1e-12);
+        // 0.3% and 1%
+        let fee_30_bps = Felt::from_hex("0xc49ba5e353f7ced916872b020c49ba").unwrap();
+        assert_eq!(pool_key(fee_30_bps, 5982).fee_percent(), "0.3");
+        let fee_100_bps = Felt::from_hex("0x28f5c28f5c28f5c28f5c28f5c28f5c2").unwrap();
+        assert_eq!(pool_key(fee_100_bps, 19802).fee_percent(), "1");
+    }
+
+    #[test]
+    fn fee_boundaries() {
+        let free = pool_key(Felt::ZERO, 1);
+        assert_eq!(free.fee_percent(), "0");
+        assert_eq!(free.fee_bps(), 0.0);
+        let max = pool_key(Felt::from(u128::MAX), 1);
+        assert_eq!(max.fee_percent(), "100");
+        assert!((max.fee_bps() - BPS).abs() < 1e-9);
+        // an invalid fee above u128 is treated as the maximum
+        let above_max = pool_key(Felt::from(u128::MAX) + Felt::ONE, 1);
+        assert_eq!(above_max.fee_q128(), u128::MAX);
+    }
+
+    #[test]
+    fn price_granularity_of_tick_spacing() {
+        assert!((pool_key(FEE_5_BPS, 1000).price_granularity() - 0.001_000_499_666).abs() < 1e-12);
+        assert!((pool_key(FEE_5_BPS, 1).price_granularity() - 0.000_001).abs() < 1e-12);
+    }
+}
@@ -0,0 +1,201 @@
+// helpers of the generated models live next to them
+#![allow(clippy::multiple_inherent_impl)]
+
+use crate::{models, TokenRegistry};
+use starknet_core::types::Felt;
+use starknet_crypto::poseidon_hash_many;
+
+impl models::RouteNode {
+    /// The token which leaves the pool for the `input` token, `None` if the pool doesn't have it
+    #[inline]
+    pub fn output_token(&self, input: Felt) -> Option<Felt> {
+        if self.pool_key.token0 == input {
+            Some(self.pool_key.token1)
+        } else if self.pool_key.token1 == input {
+            Some(self.pool_key.token0)
+        } else {
+            None
+        }
+    }
+}
+
+impl models::Quote {
+    /// Tokens from `token` through every hop, `None` if the route is not connected
+    #[inline]
+    pub fn token_path(&self, token: Felt) -> Option<Vec<Felt>> {
+        let mut path = vec![token];
+        let mut current = token;
+        for node in &self.route {
+            current = node.output_token(current)?;
+            path.push(current);
+        }
+        Some(path)
+    }
+
+    /// The Poseidon hash of Ekubo Core pool ids of the hops (`PoolKey::pool_id`),
+    /// so the same pools in the same order have the same id across quotes and restarts
+    #[inline]
+    pub fn route_id(&self) -> Felt {
+        // only pools identify hops, limits and hints change between quotes
+        let pools: Vec<Felt> = self
+            .route
+            .iter()
+            .map(|node| node.pool_key.pool_id())
+            .collect();
+        poseidon_hash_many(&pools)
+    }
+
+    /// E.g. `ETH -[0.05%]-> USDC -[0.3%]-> ETH`, unknown tokens are shown by address
+    #[inline]
+    pub fn render(&self, token: Felt, registry: &TokenRegistry) -> String {
+        let mut rendered = registry.symbol(token);
+        let mut current = Some(token);
+        for node in &self.route {
+            current = current.and_then(|input| node.output_token(input));
+            let output = current.map_or_else(|| "?".to_owned(), |output| registry.symbol(output));
+            rendered = format!("{rendered} -[{}%]-> {output}", node.pool_key.fee_percent());
+        }
+        rendered
+    }
+}
+
+impl models::Quotes {
+    /// Splits are ordered by their route ids, so the id doesn't depend on the order of splits
+    #[inline]
+    pub fn route_id(&self) -> Felt {
+        let mut ids: Vec<Felt> = self.splits.iter().map(models::Quote::route_id).collect();
+        ids.sort_unstable();
+        poseidon_hash_many(&ids)
+    }
+
+    /// Splits separated by ` | `
+    #[inline]
+    pub fn render(&self, token: Felt, registry: &TokenRegistry) -> String {
+        self.splits
+            .iter()
+            .map(|split| split.render(token, registry))
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::default_numeric_fallback,
+    clippy::separated_literal_suffix,
+    clippy::single_call_fn,
+    clippy::indexing_slicing
+)]
+mod tests {
+    use super::*;
+    use crate::{TokenAmount, TokenInfo};
+
+    const ETH: Felt = Felt::from_hex_unchecked("0x1");
+    const USDC: Felt = Felt::from_hex_unchecked("0x2");
+    const STRK: Felt = Felt::from_hex_unchecked("0x3");
+    // 0.05% and 0.3% as Q0.128 fractions
+    const FEE_5_BPS: Felt = Felt::from_hex_unchecked("0x20c49ba5e353f80000000000000000");
+    const FEE_30_BPS: Felt = Felt::from_hex_unchecked("0xc49ba5e353f7ced916872b020c49ba");
+
+    fn node(token0: Felt, token1: Felt, fee: Felt) -> models::RouteNode {
+        models::RouteNode::new(
+            models::PoolKey::new(token0, token1, fee, 1000, Felt::ZERO),
+            Felt::ONE,
+            0,
+        )
+    }
+
+    fn quote(route: Vec<models::RouteNode>) -> models::Quote {
+        models::Quote::new(Felt::from(100), TokenAmount::from(101_u128), route)
+    }
+
+    fn registry() -> TokenRegistry {
+        let mut registry = TokenRegistry::default();
+        for (symbol, address) in [("ETH", ETH), ("USDC", USDC)] {
+            registry.insert(TokenInfo {
+                symbol: symbol.to_owned(),
+                decimals: 18,
+                address,
+            });
+        }
+        registry
+    }
+
+    #[test]
+    fn token_path_follows_hops() {
+        let cycle = quote(vec![
+            node(ETH, USDC, FEE_5_BPS),
+            node(ETH, USDC, FEE_30_BPS),
+        ]);
+        assert_eq!(cycle.token_path(ETH), Some(vec![ETH, USDC, ETH]));
+        assert_eq!(cycle.token_path(STRK), None);
+        let disconnected = quote(vec![node(ETH, USDC, FEE_5_BPS), node(ETH, STRK, FEE_5_BPS)]);
+        assert_eq!(disconnected.token_path(ETH), None);
+    }
+
+    #[test]
+    fn route_id_depends_on_pools_only() {
+        let route = vec![node(ETH, USDC, FEE_5_BPS), node(ETH, USDC, FEE_30_BPS)];
+        let id = quote(route.clone()).route_id();
+        assert_eq!(
+            id,
+            poseidon_hash_many(&[route[0].pool_key.pool_id(), route[1].pool_key.pool_id()])
+        );
+        // other limits, hints and amounts of the same pools
+        let mut requoted = quote(route.clone());
+        requoted.route[0].sqrt_ratio_limit = Felt::TWO;
+        requoted.route[1].skip_ahead = 5;
+        requoted.amount = TokenAmount::from(102_u128);
+        assert_eq!(requoted.route_id(), id);
+        // the same pools in the other direction are another route
+        let reversed: Vec<_> = route.into_iter().rev().collect();
+        assert_ne!(quote(reversed).route_id(), id);
+    }
+
+    #[test]
+    fn quotes_route_id_ignores_the_order_of_splits() {
+        let first = quote(vec![
+            node(ETH, USDC, FEE_5_BPS),
+            node(ETH, USDC, FEE_30_BPS),
+        ]);
+        let second = quote(vec![
+            node(ETH, STRK, FEE_5_BPS),
+            node(ETH, STRK, FEE_30_BPS),
+        ]);
+        let quotes = models::Quotes::new(
+            TokenAmount::from(202_u128),
+            vec![first.clone(), second.clone()],
+        );
+        let swapped = models::Quotes::new(
+            TokenAmount::from(202_u128),
+            vec![second.clone(), first.clone()],
+        );
+        assert_eq!(quotes.route_id(), swapped.route_id());
+        let single = models::Quotes::new(TokenAmount::from(101_u128), vec![first]);
+        assert_ne!(quotes.route_id(), single.route_id());
+    }
+
+    #[test]
+    fn renders_symbols_and_fees() {
+        let registry = registry();
+        let cycle = quote(vec![
+            node(ETH, USDC, FEE_5_BPS),
+            node(ETH, USDC, FEE_30_BPS),
+        ]);
+        assert_eq!(
+            cycle.render(ETH, &registry),
+            "ETH -[0.05%]-> USDC -[0.3%]-> ETH"
+        );
+        let through_unknown = quote(vec![node(ETH, STRK, FEE_5_BPS), node(ETH, STRK, FEE_5_BPS)]);
+        assert_eq!(
+            through_unknown.render(ETH, &registry),
+            "ETH -[0.05%]-> 0x3 -[0.05%]-> ETH"
+        );
+        let quotes = models::Quotes::new(TokenAmount::from(202_u128), vec![cycle, through_unknown]);
+        assert_eq!(
+            quotes.render(ETH, &registry),
+            "ETH -[0.05%]-> USDC -[0.3%]-> ETH | ETH -[0.05%]-> 0x3 -[0.05%]-> ETH"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use crate::{canonical_hex, models};
+use color_eyre::eyre::{eyre, Result};
+use starknet_core::types::Felt;
+use std::collections::HashMap;
+
+/// What is needed to show an amount of a token
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TokenInfo {
+    pub symbol: String,
+    pub decimals: u8,
+    pub address: Felt,
+}
+
+/// Tokens by address
+#[derive(Clone, Debug, Default)]
+pub struct TokenRegistry {
+    tokens: HashMap<Felt, TokenInfo>,
+}
+
+impl TokenRegistry {
+    #[inline]
+    pub fn from_tokens(tokens: Vec<models::Token>) -> Result<Self> {
+        let mut registry = Self::default();
+        for token in tokens {
+            let decimals = u8::try_from(token.decimals).map_err(|e| {
+                eyre!(
+                    "token {} has invalid decimals {}: {e}",
+                    token.symbol,
+                    token.decimals
+                )
+            })?;
+            registry.insert(TokenInfo {
+                symbol: token.symbol,
+                decimals,
+                address: token.l2_token_address,
+            });
+        }
+        Ok(registry)
+    }
+
+    #[inline]
+    pub fn insert(&mut self, token: TokenInfo) {
+        self.tokens.insert(token.address, token);
+    }
+
+    #[inline]
+    pub fn get(&self, address: Felt) -> Option<&TokenInfo> {
+        self.tokens.get(&address)
+    }
+
+    /// Symbols are not unique across the listed tokens, so the first match is returned
+    #[inline]
+    pub fn by_symbol(&self, symbol: &str) -> Option<&TokenInfo> {
+        self.tokens.values().find(|token| token.symbol == symbol)
+    }
+
+    /// The symbol or the canonical hex address for unknown tokens
+    #[inline]
+    pub fn symbol(&self, address: Felt) -> String {
+        self.get(address)
+            .map_or_else(|| canonical_hex(address), |token| token.symbol.clone())
+    }
+}
@@ -0,0 +1,240 @@
+use crate::{canonical_hex, models, TokenAmount};
+use color_eyre::eyre::{bail, ensure, eyre, Result};
+use starknet_core::types::Felt;
+
+/// The lowest sqrt ratio of Ekubo Core
+pub const MIN_SQRT_RATIO: Felt = Felt::from_hex_unchecked("0x1000003f7f1380b75");
+/// The highest sqrt ratio of Ekubo Core
+pub const MAX_SQRT_RATIO: Felt =
+    Felt::from_hex_unchecked("0xfffffc080ed7b4556f3528fe26840249f4b191ef6dff7928");
+const MIN_TICK_SPACING: i32 = 1;
+/// The largest tick spacing of Ekubo Core
+pub const MAX_TICK_SPACING: i32 = 354_892;
+// fees are `u128` in Ekubo Core
+const MAX_FEE: Felt = Felt::from_hex_unchecked("0xffffffffffffffffffffffffffffffff");
+
+/// A quote of `amount` of `token` for `other_token` is what was requested:
+/// the specified amount is the requested one, the route is connected from `token` to `other_token`
+/// and every pool key and sqrt ratio limit is valid for Ekubo Core
+#[inline]
+pub fn validate_quote(
+    quote: &models::Quote,
+    amount: Felt,
+    token: Felt,
+    other_token: Felt,
+) -> Result<()> {
+    ensure!(
+        quote.specified_amount == amount,
+        "specified amount {} differs from the requested {amount}",
+        quote.specified_amount
+    );
+    validate_route(&quote.route, token, other_token)
+}
+
+/// Like `validate_quote` for every split, the specified amounts add up to `amount`
+/// and the total is the sum of the amounts of the splits
+#[inline]
+#[allow(clippy::arithmetic_side_effects)]
+pub fn validate_quotes(
+    quotes: &models::Quotes,
+    amount: Felt,
+    token: Felt,
+    other_token: Felt,
+) -> Result<()> {
+    ensure!(!quotes.splits.is_empty(), "quotes have no splits");
+    let mut specified = Felt::ZERO;
+    let mut total = TokenAmount::zero();
+    for (i, split) in quotes.splits.iter().enumerate() {
+        validate_route(&split.route, token, other_token)
+            .map_err(|e| e.wrap_err(format!("split {i}")))?;
+        // signed amounts add up in the field
+        specified += split.specified_amount;
+        total = total
+            .checked_add(split.amount)
+            .ok_or_else(|| eyre!("amounts of splits overflow u256"))?;
+    }
+    ensure!(
+        specified == amount,
+        "specified amounts of splits add up to {specified}, not the requested {amount}"
+    );
+    ensure!(
+        total == quotes.total,
+        "amounts of splits add up to {total}, not the total {}",
+        quotes.total
+    );
+    Ok(())
+}
+
+/// A pool key which Ekubo Core accepts: ordered non-zero tokens,
+/// a tick spacing from 1 to `MAX_TICK_SPACING` and a `u128` fee
+#[inline]
+pub fn validate_pool_key(pool_key: &models::PoolKey) -> Result<()> {
+    ensure!(pool_key.token0 != Felt::ZERO, "token0 is zero");
+    ensure!(
+        pool_key.token0 < pool_key.token1,
+        "token0 {} is not below token1 {}",
+        canonical_hex(pool_key.token0),
+        canonical_hex(pool_key.token1)
+    );
+    ensure!(
+        (MIN_TICK_SPACING..=MAX_TICK_SPACING).contains(&pool_key.tick_spacing),
+        "tick spacing {} is out of the range of Ekubo Core",
+        pool_key.tick_spacing
+    );
+    ensure!(
+        pool_key.fee <= MAX_FEE,
+        "fee {:#x} doesn't fit u128",
+        pool_key.fee
+    );
+    Ok(())
+}
+
+// every hop leaves the pool with the other token of the pool key
+fn validate_route(route: &[models::RouteNode], token: Felt, other_token: Felt) -> Result<()> {
+    ensure!(!route.is_empty(), "route is empty");
+    let mut current = token;
+    for (i, node) in route.iter().enumerate() {
+        let pool_key = &node.pool_key;
+        validate_pool_key(pool_key).map_err(|e| e.wrap_err(format!("hop {i}")))?;
+        current = if pool_key.token0 == current {
+            pool_key.token1
+        } else if pool_key.token1 == current {
+            pool_key.token0
+        } else {
+            bail!(
+                "hop {i} is a pool of {} and {} without {}",
+                canonical_hex(pool_key.token0),
+                canonical_hex(pool_key.token1),
+                canonical_hex(current)
+            );
+        };
+        ensure!(
+            (MIN_SQRT_RATIO..=MAX_SQRT_RATIO).contains(&node.sqrt_ratio_limit),
+            "sqrt ratio limit {} of hop {i} is out of the range of Ekubo Core",
+            node.sqrt_ratio_limit
+        );
+    }
+    ensure!(
+        current == other_token,
+        "route ends at {} instead of {}",
+        canonical_hex(current),
+        canonical_hex(other_token)
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+#[allow(
+    clippy::unwrap_used,
+    clippy::default_numeric_fallback,
+    clippy::arithmetic_side_effects,
+    clippy::separated_literal_suffix,
+    clippy::assertions_on_result_states,
+    clippy::single_call_fn,
+    clippy::indexing_slicing
+)]
+mod tests {
+    use super::*;
+
+    const TOKEN: Felt = Felt::from_hex_unchecked("0x1");
+    const OTHER_TOKEN: Felt = Felt::from_hex_unchecked("0x2");
+    const THIRD_TOKEN: Felt = Felt::from_hex_unchecked("0x3");
+    const FEE: Felt = Felt::from_hex_unchecked("0x20c49ba5e353f80000000000000000");
+
+    fn node(token0: Felt, token1: Felt) -> models::RouteNode {
+        models::RouteNode::new(
+            models::PoolKey::new(token0, token1, FEE, 1000, Felt::ZERO),
+            MAX_SQRT_RATIO,
+            0,
+        )
+    }
+
+    // TOKEN -> THIRD_TOKEN -> OTHER_TOKEN
+    fn quote(amount: u128) -> models::Quote {
+        models::Quote::new(
+            Felt::from(amount),
+            TokenAmount::from(amount * 2),
+            vec![node(TOKEN, THIRD_TOKEN), node(OTHER_TOKEN, THIRD_TOKEN)],
+        )
+    }
+
+    #[test]
+    fn accepts_a_connected_route() {
+        assert!(validate_quote(&quote(100), Felt::from(100), TOKEN, OTHER_TOKEN).is_ok());
+        // an arbitrage cycle ends at the same token
+        let cycle = models::Quote::new(
+            Felt::from(100),
+            TokenAmount::from(101_u128),
+            vec![node(TOKEN, OTHER_TOKEN), node(TOKEN, OTHER_TOKEN)],
+        );
+        assert!(validate_quote(&cycle, Felt::from(100), TOKEN, TOKEN).is_ok());
+    }
+
+    #[test]
+    fn rejects_other_requests() {
+        assert!(validate_quote(&quote(100), Felt::from(99), TOKEN, OTHER_TOKEN).is_err());
+        assert!(validate_quote(&quote(100), Felt::from(100), TOKEN, THIRD_TOKEN).is_err());
+        assert!(validate_quote(&quote(100), Felt::from(100), THIRD_TOKEN, OTHER_TOKEN).is_err());
+        let empty = models::Quote::new(Felt::from(100), TokenAmount::zero(), vec![]);
+        assert!(validate_quote(&empty, Felt::from(100), TOKEN, TOKEN).is_err());
+    }
+
+    #[test]
+    fn sqrt_ratio_limit_boundaries() {
+        let mut quote = quote(100);
+        for limit in [MIN_SQRT_RATIO, MAX_SQRT_RATIO] {
+            quote.route[0].sqrt_ratio_limit = limit;
+            assert!(validate_quote(&quote, Felt::from(100), TOKEN, OTHER_TOKEN).is_ok());
+        }
+        for limit in [
+            Felt::ZERO,
+            MIN_SQRT_RATIO - Felt::ONE,
+            MAX_SQRT_RATIO + Felt::ONE,
+        ] {
+            quote.route[0].sqrt_ratio_limit = limit;
+            assert!(validate_quote(&quote, Felt::from(100), TOKEN, OTHER_TOKEN).is_err());
+        }
+    }
+
+    #[test]
+    fn pool_key_boundaries() {
+        let key = |token0, token1, tick_spacing, fee| {
+            models::PoolKey::new(token0, token1, fee, tick_spacing, Felt::ZERO)
+        };
+        assert!(validate_pool_key(&key(TOKEN, OTHER_TOKEN, 1, FEE)).is_ok());
+        assert!(validate_pool_key(&key(TOKEN, OTHER_TOKEN, MAX_TICK_SPACING, FEE)).is_ok());
+        assert!(validate_pool_key(&key(TOKEN, OTHER_TOKEN, 0, FEE)).is_err());
+        assert!(validate_pool_key(&key(TOKEN, OTHER_TOKEN, -1, FEE)).is_err());
+        assert!(validate_pool_key(&key(TOKEN, OTHER_TOKEN, MAX_TICK_SPACING + 1, FEE)).is_err());
+        assert!(validate_pool_key(&key(TOKEN, OTHER_TOKEN, 1, MAX_FEE)).is_ok());
+        assert!(validate_pool_key(&key(TOKEN, OTHER_TOKEN, 1, MAX_FEE + Felt::ONE)).is_err());
+        // tokens are ordered and non-zero
+        assert!(validate_pool_key(&key(OTHER_TOKEN, TOKEN, 1, FEE)).is_err());
+        assert!(validate_pool_key(&key(TOKEN, TOKEN, 1, FEE)).is_err());
+        assert!(validate_pool_key(&key(Felt::ZERO, TOKEN, 1, FEE)).is_err());
+    }
+
+    #[test]
+    fn splits_add_up() {
+        let quotes = models::Quotes::new(TokenAmount::from(300_u128), vec![quote(100), quote(50)]);
+        assert!(validate_quotes(&quotes, Felt::from(150), TOKEN, OTHER_TOKEN).is_ok());
+        assert!(validate_quotes(&quotes, Felt::from(100), TOKEN, OTHER_TOKEN).is_err());
+        let wrong_total =
+            models::Quotes::new(TokenAmount::from(299_u128), vec![quote(100), quote(50)]);
+        assert!(validate_quotes(&wrong_total, Felt::from(150), TOKEN, OTHER_TOKEN).is_err());
+        let no_splits = models::Quotes::new(TokenAmount::zero(), vec![]);
+        assert!(validate_quotes(&no_splits, Felt::ZERO, TOKEN, OTHER_TOKEN).is_err());
+        let overflow = models::Quotes::new(
+            TokenAmount::zero(),
+            vec![
+                models::Quote::new(
+                    Felt::ONE,
+                    TokenAmount::from_words(u128::MAX, u128::MAX),
+                    quote(1).route,
+                ),
+                quote(1),
+            ],
+        );
+        assert!(validate_quotes(&overflow, Felt::TWO, TOKEN, OTHER_TOKEN).is_err());
+    }
+}